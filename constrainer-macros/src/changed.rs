@@ -0,0 +1,143 @@
+use proc_macro2::{
+    Ident,
+    Literal,
+    Span,
+    TokenStream,
+};
+use quote::{
+    TokenStreamExt,
+    quote,
+};
+
+use syn::Visibility;
+
+use std::collections::BTreeMap;
+
+use crate::graph::{
+    Graph,
+    Identifier,
+};
+use crate::propagate::Dirty;
+
+pub fn changed_name(name: &Ident) -> Ident {
+    Ident::new(&format!("{}Changed", name), Span::call_site())
+}
+
+// One bit per dynamic and constrained, in declaration order.
+fn bits(graph: &Graph) -> BTreeMap<usize, usize> {
+    graph.identifiers.values().enumerate()
+        .filter(|(_, identifier)| matches!(identifier, Identifier::Dynamic(_) | Identifier::Constrained(_)) && !identifier.is_hidden())
+        .enumerate()
+        .map(|(bit, (index, _))| (index, bit))
+        .collect()
+}
+
+fn words(bits: &BTreeMap<usize, usize>) -> usize {
+    bits.len().div_ceil(64).max(1)
+}
+
+// The struct returned by setters and transactions, with a method per dynamic and constrained
+// telling whether it was set or recomputed.
+pub fn changed_struct(graph: &Graph, vis: &Visibility, name: &Ident) -> TokenStream {
+    let changed_name = changed_name(name);
+    let bits = bits(graph);
+    let words = words(&bits);
+
+    let mut ops = TokenStream::new();
+    let mut debug_entries = TokenStream::new();
+    for (index, bit) in &bits {
+        let field_name = graph.identifiers.get_index(*index).unwrap().0;
+        let word = bit/64;
+        let mask = Literal::u64_suffixed(1 << (bit%64));
+        ops.append_all(quote! {
+            pub fn #field_name(&self) -> bool {
+                self.bits[#word] & #mask != 0
+            }
+        });
+        let field_name_str = field_name.to_string();
+        debug_entries.append_all(quote! {
+            if self.#field_name() {
+                set.entry(&::core::format_args!(#field_name_str));
+            }
+        });
+    }
+
+    quote! {
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+        #vis struct #changed_name {
+            bits: [u64; #words],
+        }
+
+        impl #changed_name {
+            #ops
+
+            pub fn is_empty(&self) -> bool {
+                self.bits.iter().all(|word| *word == 0)
+            }
+        }
+
+        impl ::core::ops::BitOr for #changed_name {
+            type Output = Self;
+
+            fn bitor(mut self, other: Self) -> Self {
+                self |= other;
+                self
+            }
+        }
+
+        impl ::core::ops::BitOrAssign for #changed_name {
+            fn bitor_assign(&mut self, other: Self) {
+                for (word, other_word) in self.bits.iter_mut().zip(other.bits) {
+                    *word |= other_word;
+                }
+            }
+        }
+
+        impl ::core::fmt::Debug for #changed_name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.write_str(::core::concat!(::core::stringify!(#changed_name), " "))?;
+                let mut set = f.debug_set();
+                #debug_entries
+                set.finish()
+            }
+        }
+    }
+}
+
+// An expression for the value of the struct once everything in `changed` was propagated.
+pub fn changed_value(graph: &Graph, name: &Ident, changed: &BTreeMap<usize, Dirty>) -> TokenStream {
+    let changed_name = changed_name(name);
+    let bits = bits(graph);
+    let mut constants = vec![0u64; words(&bits)];
+    let mut conditionals = vec![Vec::new(); constants.len()];
+    for (index, dirty) in changed {
+        let bit = match bits.get(index) {
+            Some(bit) => *bit,
+            None => continue,
+        };
+        match dirty {
+            Dirty::Always => constants[bit/64] |= 1 << (bit%64),
+            Dirty::When(flag) => {
+                let mask = Literal::u64_suffixed(1 << (bit%64));
+                conditionals[bit/64].push(quote! {
+                    if #flag { #mask } else { 0 }
+                });
+            },
+        }
+    }
+    let words = constants.into_iter().zip(conditionals).map(|(constant, conditionals)| {
+        let literal = Literal::u64_suffixed(constant);
+        if conditionals.is_empty() {
+            quote! { #literal }
+        } else if constant == 0 {
+            quote! { #(#conditionals)|* }
+        } else {
+            quote! { #literal #(| #conditionals)* }
+        }
+    });
+    quote! {
+        #changed_name {
+            bits: [#(#words,)*],
+        }
+    }
+}
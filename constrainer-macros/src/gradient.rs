@@ -0,0 +1,115 @@
+use proc_macro2::{
+    Ident,
+    Span,
+    TokenStream,
+};
+use quote::{
+    ToTokens,
+    TokenStreamExt,
+    quote,
+};
+use syn::{
+    parse_quote,
+    Type,
+};
+
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+use crate::graph::{
+    External,
+    Graph,
+    Identifier,
+};
+use crate::numeric::{
+    is_f64_dynamic,
+    numeric_constraineds,
+    numeric_fn,
+};
+use crate::propagate::pass;
+
+// `grad_<constrained>_wrt_<dynamic>` for every numeric constrained and `f64` dynamic it
+// depends on, evaluating the body of the constrained and of those in between over `Dual`
+// with the dynamic as the variable. Externals are constants, passed to the method.
+pub fn gradients(graph: &Graph) -> TokenStream {
+    let identifiers = &graph.identifiers;
+    let differentiable = numeric_constraineds(graph);
+    let dual: Type = parse_quote! { ::constrainer::Dual };
+    let mut ops = TokenStream::new();
+
+    for index in &differentiable {
+        let (name, identifier) = identifiers.get_index(*index).unwrap();
+        if let Identifier::Constrained(constrained) = identifier {
+            ops.append_all(numeric_fn(graph, constrained, &dual_fn_name(name), &dual));
+        }
+    }
+
+    for index in &differentiable {
+        let name = identifiers.get_index(*index).unwrap().0;
+        let ancestors = ancestors(graph, *index);
+        for variable in &ancestors {
+            let (variable_name, variable_identifier) = identifiers.get_index(*variable).unwrap();
+            if !is_f64_dynamic(variable_identifier) {
+                continue;
+            }
+
+            let mut external_args = BTreeMap::new();
+            let mut block = TokenStream::new();
+            for ancestor in graph.order.iter().filter(|ancestor| ancestors.contains(ancestor) || *ancestor == index) {
+                let (ancestor_name, ancestor_identifier) = identifiers.get_index(*ancestor).unwrap();
+                match ancestor_identifier {
+                    Identifier::Dynamic(_) if ancestor == variable => block.append_all(quote! {
+                        let #ancestor_name = ::constrainer::Dual::variable(self.#ancestor_name);
+                    }),
+                    Identifier::Dynamic(_) => block.append_all(quote! {
+                        let #ancestor_name = ::constrainer::Dual::constant(self.#ancestor_name);
+                    }),
+                    Identifier::Constrained(constrained) => {
+                        let dual_fn_name = dual_fn_name(ancestor_name);
+                        let args = constrained.params.iter().map(|param| pass(param.name.to_token_stream(), param.passing));
+                        block.append_all(quote! {
+                            let #ancestor_name = Self::#dual_fn_name(#(#args,)*);
+                        });
+                    },
+                    Identifier::External(External { ty, .. }) => {
+                        external_args.insert(*ancestor, quote! {
+                            #ancestor_name: #ty,
+                        });
+                    },
+                    Identifier::State(_) | Identifier::Check(_) | Identifier::Listener(_) => unreachable!(),
+                }
+            }
+            let grad_fn_name = Ident::new(&format!("grad_{}_wrt_{}", name, variable_name), Span::call_site());
+            let external_args = external_args.into_values();
+            ops.append_all(quote! {
+                pub fn #grad_fn_name(&self, #(#external_args)*) -> f64 {
+                    #block
+                    #name.derivative
+                }
+            });
+        }
+    }
+
+    ops
+}
+
+fn dual_fn_name(name: &Ident) -> Ident {
+    Ident::new(&format!("dual_{}", name), Span::call_site())
+}
+
+// Everything `index` transitively depends on.
+fn ancestors(graph: &Graph, index: usize) -> BTreeSet<usize> {
+    let mut ancestors = BTreeSet::new();
+    let mut queue = vec![index];
+    while let Some(index) = queue.pop() {
+        for param in graph.identifiers[index].params() {
+            let param_index = graph.identifiers.get_index_of(&param.name).unwrap();
+            if ancestors.insert(param_index) {
+                queue.push(param_index);
+            }
+        }
+    }
+    ancestors
+}
@@ -0,0 +1,536 @@
+use proc_macro2::{
+    Ident,
+    TokenStream,
+};
+use syn::Type;
+
+use crate::parse::{
+    InverseAssignment,
+    Param,
+    Passing,
+};
+
+use indexmap::IndexMap;
+use std::collections::BTreeSet;
+
+#[derive(Debug)]
+pub enum Identifier {
+    Dynamic(Dynamic),
+    Constrained(Constrained),
+    External(External),
+    State(State),
+    Check(Check),
+    Listener(Listener),
+}
+
+impl Identifier {
+    pub fn params(&self) -> &[Param] {
+        match self {
+            Identifier::Constrained(constrained) => &constrained.params,
+            Identifier::Check(check) => &check.params,
+            Identifier::Listener(listener) => &listener.params,
+            Identifier::Dynamic(_) | Identifier::External(_) | Identifier::State(_) => &[],
+        }
+    }
+
+    pub fn dependents(&self) -> &BTreeSet<usize> {
+        match self {
+            Identifier::Dynamic(dynamic) => &dynamic.dependents,
+            Identifier::Constrained(constrained) => &constrained.dependents,
+            Identifier::External(external) => &external.dependents,
+            Identifier::State(state) => &state.dependents,
+            Identifier::Check(check) => &check.dependents,
+            Identifier::Listener(listener) => &listener.dependents,
+        }
+    }
+
+    fn dependents_mut(&mut self) -> &mut BTreeSet<usize> {
+        match self {
+            Identifier::Dynamic(dynamic) => &mut dynamic.dependents,
+            Identifier::Constrained(constrained) => &mut constrained.dependents,
+            Identifier::External(external) => &mut external.dependents,
+            Identifier::State(state) => &mut state.dependents,
+            Identifier::Check(check) => &mut check.dependents,
+            Identifier::Listener(listener) => &mut listener.dependents,
+        }
+    }
+
+    pub fn is_lazy(&self) -> bool {
+        matches!(self, Identifier::Constrained(Constrained { lazy: true, .. }))
+    }
+
+    // Internal to the constrainer: it has no getter, bit in the change set or subscribers.
+    pub fn is_hidden(&self) -> bool {
+        matches!(self, Identifier::Constrained(Constrained { solve: Some(_), .. }))
+    }
+
+    pub fn ty(&self) -> Option<&Type> {
+        match self {
+            Identifier::Dynamic(Dynamic { ty, .. }) |
+            Identifier::Constrained(Constrained { ty, .. }) |
+            Identifier::External(External { ty, .. }) |
+            Identifier::State(State { ty, .. }) => Some(ty),
+            Identifier::Check(_) | Identifier::Listener(_) => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Identifier::Dynamic(_) => "dynamic",
+            Identifier::Constrained(_) => "constrained",
+            Identifier::External(_) => "external",
+            Identifier::State(_) => "state",
+            Identifier::Check(_) => "check",
+            Identifier::Listener(_) => "listener",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Dynamic {
+    pub ty: Type,
+    pub normalize: Option<Normalize>,
+    pub dependents: BTreeSet<usize>,
+}
+
+// Applied to every value given for a dynamic before it is checked or stored.
+#[derive(Debug)]
+pub struct Normalize {
+    pub normalize_fn_name: Ident,
+    // Takes the dynamic by value and returns it normalized.
+    pub block: TokenStream,
+}
+
+#[derive(Debug)]
+pub struct Constrained {
+    pub ty: Type,
+    // Stored in a `OnceCell` that is emptied by propagation and filled by the getter.
+    pub lazy: bool,
+    pub params: Vec<Param>,
+    // The block returns a `Result` of `ty`.
+    pub fallible: bool,
+    pub block: TokenStream,
+    pub compute_fn_name: Ident,
+    pub inverse: Option<Inverse>,
+    // Set on the hidden constrained holding the solution of a `solve` item, whose compute
+    // function also takes the previous solution to start from.
+    pub solve: Option<Box<Solve>>,
+    pub dependents: BTreeSet<usize>,
+}
+
+// Lets a constrained be set, by computing dynamics it depends on back from its value.
+#[derive(Debug)]
+pub struct Inverse {
+    pub invert_fn_name: Ident,
+    pub assignments: Vec<InverseAssignment>,
+}
+
+// Equations solved for unknowns, each of which is a constrained reading its value out of the
+// solution.
+#[derive(Debug)]
+pub struct Solve {
+    pub unknowns: Vec<Ident>,
+    // How the unknowns and the equations of `residuals` are named by reports and errors.
+    pub unknown_names: Vec<String>,
+    pub equation_names: Vec<String>,
+    // Starting values of the unknowns in `new`.
+    pub guesses: Vec<TokenStream>,
+    // Statements run once the unknowns are bound, for the residuals to use.
+    pub bindings: TokenStream,
+    pub residuals: Vec<TokenStream>,
+    // Writes the residuals for given unknowns, for both the solver and `dof_report`.
+    pub residuals_fn_name: Ident,
+    // What the error names when no solution is found, such as `(a, b)`.
+    pub description: String,
+}
+
+#[derive(Debug)]
+pub struct External {
+    pub ty: Type,
+    pub dependents: BTreeSet<usize>,
+}
+
+// Stored on the constrainer but only ever read or modified by listeners, so it never causes
+// anything to be recomputed.
+#[derive(Debug)]
+pub struct State {
+    pub ty: Type,
+    pub dependents: BTreeSet<usize>,
+}
+
+// A condition on dynamics, verified before they are stored.
+#[derive(Debug)]
+pub struct Check {
+    pub params: Vec<Param>,
+    // A `bool` expression.
+    pub block: TokenStream,
+    pub check_fn_name: Ident,
+    // What the error names when the check fails.
+    pub description: String,
+    pub dependents: BTreeSet<usize>,
+}
+
+#[derive(Debug)]
+pub struct Listener {
+    pub params: Vec<Param>,
+    pub block: TokenStream,
+    pub dependents: BTreeSet<usize>,
+}
+
+#[derive(Debug)]
+pub struct Graph {
+    pub identifiers: IndexMap<Ident, Identifier>,
+    // Every identifier, each one after all of its params.
+    pub order: Vec<usize>,
+    // Identifiers a listener takes as `changed`, whose previous value has to be kept around
+    // while propagating.
+    pub tracked: BTreeSet<usize>,
+    // Whether a constrained or check can fail, in which case `new`, setters and transactions return a
+    // `Result` and restore what they changed on failure.
+    pub fallible: bool,
+}
+
+impl Graph {
+    // Validates every name used by the constrainer. All problems found are reported together
+    // rather than stopping at the first one.
+    pub fn new(declarations: Vec<(Ident, Identifier)>, setter_dynamics: &[&Ident]) -> syn::Result<Self> {
+        let mut errors = Errors::default();
+
+        let mut identifiers: IndexMap<Ident, Identifier> = IndexMap::new();
+        for (name, identifier) in declarations {
+            // Dynamics and constraineds get a method of their name on the `Changed` struct.
+            if name == "is_empty" && matches!(identifier, Identifier::Dynamic(_) | Identifier::Constrained(_)) {
+                errors.push(syn::Error::new(name.span(), "`is_empty` is reserved, it tells whether nothing changed"));
+            }
+            if let Some((previous, _)) = identifiers.get_key_value(&name) {
+                errors.push(syn::Error::new(name.span(), format!("`{}` is already declared", name)));
+                errors.push(syn::Error::new(previous.span(), format!("`{}` is first declared here", name)));
+            } else {
+                identifiers.insert(name, identifier);
+            }
+        }
+
+        let mut edges = Vec::new();
+        let mut tracked = BTreeSet::new();
+        for (index, (name, identifier)) in identifiers.iter().enumerate() {
+            for Param { name: param, passing } in identifier.params() {
+                let (param_index, _, param_identifier) = match identifiers.get_full(param) {
+                    Some(found) => found,
+                    None => {
+                        let candidates = identifiers.iter()
+                            .filter(|(_, identifier)| !matches!(identifier, Identifier::Check(_) | Identifier::Listener(_)))
+                            .map(|(name, _)| name);
+                        errors.push(unknown_identifier(param, candidates));
+                        continue;
+                    },
+                };
+                if let Identifier::Check(_) | Identifier::Listener(_) = param_identifier {
+                    errors.push(syn::Error::new(param.span(), format!("A {} cannot depend on a {}.", identifier.kind(), param_identifier.kind())));
+                } else if matches!(identifier, Identifier::Check(_)) && !matches!(param_identifier, Identifier::Dynamic(_)) {
+                    errors.push(syn::Error::new(param.span(), "A check can only depend on dynamics, it runs before anything is recomputed."));
+                } else if let (Identifier::Constrained(Constrained { lazy: true, .. }), Identifier::External(_)) = (identifier, param_identifier) {
+                    errors.push(syn::Error::new(param.span(), "A lazy constrained cannot depend on an external."));
+                } else if let (Identifier::Constrained(_), Identifier::State(_)) = (identifier, param_identifier) {
+                    errors.push(syn::Error::new(param.span(), "A constrained cannot depend on state."));
+                } else if *passing == Passing::Changed && !matches!(identifier, Identifier::Listener(_)) {
+                    errors.push(syn::Error::new(param.span(), "Only listeners can take a change."));
+                } else if *passing == Passing::Changed && matches!(param_identifier, Identifier::External(_) | Identifier::State(_)) {
+                    errors.push(syn::Error::new(param.span(), format!("`{}` has no previous value to take a change of.", param)));
+                } else if *passing == Passing::Mut && !matches!(param_identifier, Identifier::State(_)) {
+                    errors.push(syn::Error::new(param.span(), "Only state can be taken by `mut`, everything else is updated by the constrainer."));
+                } else if *passing == Passing::Changed && param_identifier.is_lazy() {
+                    errors.push(syn::Error::new(param.span(), "A lazy constrained may never have been computed, so it has no previous value to take a change of."));
+                } else if param_index == index {
+                    errors.push(syn::Error::new(param.span(), format!("`{}` cannot depend on itself", name)));
+                } else {
+                    edges.push((param_index, index));
+                    if *passing == Passing::Changed {
+                        tracked.insert(param_index);
+                    }
+                }
+            }
+        }
+        for (name, identifier) in &identifiers {
+            let inverse = match identifier {
+                Identifier::Constrained(Constrained { inverse: Some(inverse), .. }) => inverse,
+                _ => continue,
+            };
+            let mut set = BTreeSet::new();
+            for InverseAssignment { dynamic, .. } in &inverse.assignments {
+                let is_param = identifier.params().iter().any(|param| param.name == *dynamic);
+                if !is_param || !matches!(identifiers.get(dynamic), Some(Identifier::Dynamic(_))) {
+                    errors.push(syn::Error::new(dynamic.span(), format!("An inverse can only set dynamics `{}` depends on.", name)));
+                } else if !set.insert(dynamic) {
+                    errors.push(syn::Error::new(dynamic.span(), format!("`{}` is already set by this inverse", dynamic)));
+                }
+            }
+        }
+        for (param_index, index) in edges {
+            identifiers[param_index].dependents_mut().insert(index);
+        }
+
+        for dynamic in setter_dynamics {
+            match identifiers.get(*dynamic) {
+                Some(Identifier::Dynamic(_)) => {},
+                Some(_) => errors.push(syn::Error::new(dynamic.span(), "Only dynamics can be set")),
+                None => {
+                    let candidates = identifiers.iter()
+                        .filter(|(_, identifier)| matches!(identifier, Identifier::Dynamic(_)))
+                        .map(|(name, _)| name);
+                    errors.push(unknown_identifier(dynamic, candidates));
+                },
+            }
+        }
+
+        let order = topological_order(&identifiers);
+        if order.len() != identifiers.len() {
+            for cycle in cycles(&identifiers) {
+                let (name, _) = identifiers.get_index(cycle[0]).unwrap();
+                let path = cycle.iter()
+                    .chain(Some(&cycle[0]))
+                    .map(|index| format!("`{}`", identifiers.get_index(*index).unwrap().0))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                errors.push(syn::Error::new(name.span(), format!("Dependency cycle: {}", path)));
+            }
+        }
+
+        errors.finish()?;
+
+        let fallible = identifiers.values().any(|identifier| matches!(identifier, Identifier::Constrained(Constrained { fallible: true, .. }) | Identifier::Check(_)));
+        Ok(Self {
+            identifiers,
+            order,
+            tracked,
+            fallible,
+        })
+    }
+
+    // Whether the previous value of an identifier is kept while propagating, either for a
+    // listener taking a change of it or to restore it if a constrained fails.
+    pub fn keeps_old(&self, index: usize) -> bool {
+        self.tracked.contains(&index) || self.fallible
+    }
+
+    // Every identifier that transitively depends on one of `sources`, in topological order.
+    pub fn affected(&self, sources: &BTreeSet<usize>) -> Vec<usize> {
+        let mut affected = BTreeSet::new();
+        let mut queue: Vec<usize> = sources.iter().copied().collect();
+        while let Some(index) = queue.pop() {
+            for dependent in self.identifiers[index].dependents() {
+                if affected.insert(*dependent) {
+                    queue.push(*dependent);
+                }
+            }
+        }
+        self.order.iter().copied().filter(|index| affected.contains(index)).collect()
+    }
+}
+
+#[derive(Default)]
+pub struct Errors(Option<syn::Error>);
+
+impl Errors {
+    pub fn push(&mut self, error: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+
+    pub fn finish(self) -> syn::Result<()> {
+        match self.0 {
+            Some(errors) => Err(errors),
+            None => Ok(()),
+        }
+    }
+}
+
+fn unknown_identifier<'a>(ident: &Ident, candidates: impl Iterator<Item = &'a Ident>) -> syn::Error {
+    let name = ident.to_string();
+    let suggestion = candidates
+        .map(|candidate| {
+            let candidate = candidate.to_string();
+            (edit_distance(&name, &candidate), candidate)
+        })
+        .filter(|(distance, _)| *distance <= (name.len()/3).max(1) && *distance < name.len())
+        .min_by_key(|(distance, _)| *distance);
+    match suggestion {
+        Some((_, candidate)) => syn::Error::new(ident.span(), format!("Unknown identifier `{}`. Did you mean `{}`?", name, candidate)),
+        None => syn::Error::new(ident.span(), format!("Unknown identifier `{}`", name)),
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i+1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + (a_char != *b_char) as usize;
+            current.push(substitution.min(previous[j+1]+1).min(current[j]+1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Kahn's algorithm, always taking the earliest declared identifier that is ready so that
+// constrainers declared in dependency order keep their declaration order. Identifiers that
+// are part of or depend on a cycle are left out.
+fn topological_order(identifiers: &IndexMap<Ident, Identifier>) -> Vec<usize> {
+    let mut remaining_params = vec![0; identifiers.len()];
+    for identifier in identifiers.values() {
+        for dependent in identifier.dependents() {
+            remaining_params[*dependent] += 1;
+        }
+    }
+    let mut ready: BTreeSet<usize> = remaining_params.iter().enumerate()
+        .filter(|(_, count)| **count == 0)
+        .map(|(index, _)| index)
+        .collect();
+    let mut order = Vec::with_capacity(identifiers.len());
+    while let Some(index) = ready.pop_first() {
+        order.push(index);
+        for dependent in identifiers[index].dependents() {
+            remaining_params[*dependent] -= 1;
+            if remaining_params[*dependent] == 0 {
+                ready.insert(*dependent);
+            }
+        }
+    }
+    order
+}
+
+// One cycle per strongly connected component (Tarjan's algorithm), each starting at its
+// earliest declared identifier and listed in the direction values flow.
+fn cycles(identifiers: &IndexMap<Ident, Identifier>) -> Vec<Vec<usize>> {
+    struct Tarjan<'a> {
+        identifiers: &'a IndexMap<Ident, Identifier>,
+        next_index: usize,
+        indices: Vec<Option<usize>>,
+        low_links: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, node: usize) {
+            self.indices[node] = Some(self.next_index);
+            self.low_links[node] = self.next_index;
+            self.next_index += 1;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+            for dependent in self.identifiers[node].dependents() {
+                match self.indices[*dependent] {
+                    None => {
+                        self.visit(*dependent);
+                        self.low_links[node] = self.low_links[node].min(self.low_links[*dependent]);
+                    },
+                    Some(index) if self.on_stack[*dependent] => {
+                        self.low_links[node] = self.low_links[node].min(index);
+                    },
+                    Some(_) => {},
+                }
+            }
+            if Some(self.low_links[node]) == self.indices[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                if component.len() > 1 {
+                    self.components.push(component);
+                }
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        identifiers,
+        next_index: 0,
+        indices: vec![None; identifiers.len()],
+        low_links: vec![0; identifiers.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; identifiers.len()],
+        components: Vec::new(),
+    };
+    for node in 0..identifiers.len() {
+        if tarjan.indices[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+
+    let mut cycles: Vec<Vec<usize>> = tarjan.components.into_iter()
+        .map(|component| {
+            let component: BTreeSet<usize> = component.into_iter().collect();
+            let start = *component.first().unwrap();
+            let mut path = vec![start];
+            loop {
+                let current = *path.last().unwrap();
+                let next = *identifiers[current].dependents().iter()
+                    .find(|dependent| component.contains(dependent))
+                    .unwrap();
+                if let Some(position) = path.iter().position(|index| *index == next) {
+                    let mut cycle = path.split_off(position);
+                    let first_declared = cycle.iter().enumerate().min_by_key(|(_, index)| **index).unwrap().0;
+                    cycle.rotate_left(first_declared);
+                    return cycle;
+                }
+                path.push(next);
+            }
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    // Identifiers named `a`, `b`, ... where each one's dependents are given by index.
+    fn identifiers(dependents: &[&[usize]]) -> IndexMap<Ident, Identifier> {
+        dependents.iter().enumerate()
+            .map(|(index, dependents)| {
+                let name = Ident::new(&((b'a'+index as u8) as char).to_string(), Span::call_site());
+                (name, Identifier::Dynamic(Dynamic {
+                    ty: syn::parse_quote!(f32),
+                    normalize: None,
+                    dependents: dependents.iter().copied().collect(),
+                }))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("width", "width"), 0);
+        assert_eq!(edit_distance("widht", "width"), 2);
+        assert_eq!(edit_distance("heigt", "height"), 1);
+        assert_eq!(edit_distance("area", "areas"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn topological_order_keeps_declaration_order_when_possible() {
+        // `a` feeds `c`, which feeds `b`; `d` is independent.
+        assert_eq!(topological_order(&identifiers(&[&[2], &[], &[1], &[]])), vec![0, 2, 1, 3]);
+        assert_eq!(topological_order(&identifiers(&[&[1], &[2], &[], &[]])), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn topological_order_leaves_out_cycles_and_their_dependents() {
+        // `b` and `c` depend on each other, `d` on `c`.
+        let identifiers = identifiers(&[&[1], &[2], &[1, 3], &[]]);
+        assert_eq!(topological_order(&identifiers), vec![0]);
+        assert_eq!(cycles(&identifiers), vec![vec![1, 2]]);
+    }
+}
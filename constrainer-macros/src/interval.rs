@@ -0,0 +1,128 @@
+use proc_macro2::{
+    Ident,
+    Span,
+    TokenStream,
+};
+use quote::{
+    ToTokens,
+    TokenStreamExt,
+    quote,
+};
+use syn::{
+    parse_quote,
+    Type,
+    Visibility,
+};
+
+use std::collections::BTreeMap;
+
+use crate::graph::{
+    External,
+    Graph,
+    Identifier,
+};
+use crate::numeric::{
+    is_f64_dynamic,
+    numeric_constraineds,
+    numeric_fn,
+};
+use crate::propagate::pass;
+
+pub struct IntervalView {
+    // The view struct and its impl.
+    pub items: TokenStream,
+    // `interval_view` and the bodies it evaluates, for the impl of the constrainer.
+    pub ops: TokenStream,
+}
+
+fn interval_view_name(name: &Ident) -> Ident {
+    Ident::new(&format!("{}IntervalView", name), Span::call_site())
+}
+
+// A struct holding an `Interval` for every `f64` dynamic and every constrained that can be
+// computed from them, built by `interval_view` from intervals for the dynamics by evaluating
+// the bodies of the constraineds over `Interval`.
+pub fn interval_view(graph: &Graph, vis: &Visibility, name: &Ident) -> IntervalView {
+    let identifiers = &graph.identifiers;
+    let view_name = interval_view_name(name);
+    let numeric = numeric_constraineds(graph);
+    let interval: Type = parse_quote! { ::constrainer::Interval };
+
+    let mut fields = TokenStream::new();
+    let mut deliminated_fields = TokenStream::new();
+    let mut getters = TokenStream::new();
+    let mut dynamic_args = TokenStream::new();
+    for (index, (field_name, identifier)) in identifiers.iter().enumerate() {
+        if !is_f64_dynamic(identifier) && !numeric.contains(&index) {
+            continue;
+        }
+        fields.append_all(quote! {
+            #field_name: #interval,
+        });
+        deliminated_fields.append_all(quote! {
+            #field_name,
+        });
+        let get_fn_name = Ident::new(&format!("get_{}", field_name), Span::call_site());
+        getters.append_all(quote! {
+            pub fn #get_fn_name(&self) -> &#interval {
+                &self.#field_name
+            }
+        });
+        if is_f64_dynamic(identifier) {
+            dynamic_args.append_all(quote! {
+                #field_name: #interval,
+            });
+        }
+    }
+
+    let mut ops = TokenStream::new();
+    let mut external_args = BTreeMap::new();
+    let mut block = TokenStream::new();
+    for index in graph.order.iter().filter(|index| numeric.contains(index)) {
+        let (constrained_name, identifier) = identifiers.get_index(*index).unwrap();
+        let constrained = if let Identifier::Constrained(constrained) = identifier {
+            constrained
+        } else {
+            unreachable!()
+        };
+        let interval_fn_name = Ident::new(&format!("interval_{}", constrained_name), Span::call_site());
+        ops.append_all(numeric_fn(graph, constrained, &interval_fn_name, &interval));
+        for param in &constrained.params {
+            let (param_index, param_name, param_identifier) = identifiers.get_full(&param.name).unwrap();
+            if let Identifier::External(External { ty, .. }) = param_identifier {
+                external_args.insert(param_index, quote! {
+                    #param_name: #ty,
+                });
+            }
+        }
+        let args = constrained.params.iter().map(|param| pass(param.name.to_token_stream(), param.passing));
+        block.append_all(quote! {
+            let #constrained_name = Self::#interval_fn_name(#(#args,)*);
+        });
+    }
+    let external_args = external_args.into_values();
+    ops.append_all(quote! {
+        pub fn interval_view(#dynamic_args #(#external_args)*) -> #view_name {
+            #block
+            #view_name {
+                #deliminated_fields
+            }
+        }
+    });
+
+    let items = quote! {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        #vis struct #view_name {
+            #fields
+        }
+
+        impl #view_name {
+            #getters
+        }
+    };
+
+    IntervalView {
+        items,
+        ops,
+    }
+}
@@ -0,0 +1,916 @@
+use proc_macro2::{
+    Ident,
+    TokenStream,
+    TokenTree,
+    Span
+};
+use quote::{
+    ToTokens,
+    TokenStreamExt,
+    quote
+};
+use syn::{
+    parse_macro_input,
+    parse_quote,
+    Attribute,
+    Generics,
+    ItemStruct,
+};
+
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+mod changed;
+mod gradient;
+mod graph;
+mod interval;
+mod numeric;
+mod parse;
+mod propagate;
+mod sketch;
+mod subscription;
+mod transaction;
+
+use changed::{
+    changed_name,
+    changed_struct,
+    changed_value,
+};
+use gradient::gradients;
+use interval::interval_view;
+use graph::{
+    Check,
+    Constrained,
+    Dynamic,
+    External,
+    Graph,
+    Identifier,
+    Inverse,
+    Listener,
+    Normalize,
+    Solve,
+    State,
+};
+use parse::{
+    ConstrainerInput,
+    Item,
+    Modes,
+    Param,
+    Passing,
+};
+use propagate::{
+    compute,
+    old_value,
+    output,
+    output_value,
+    param_ty,
+    pass,
+    place,
+    propagate,
+    verify,
+    Dirty,
+};
+use sketch::sketch;
+use subscription::{
+    subscriptions,
+    Subscriptions,
+};
+use transaction::transaction;
+
+#[proc_macro]
+pub fn create_constrainer(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as ConstrainerInput);
+    match expand(input) {
+        Ok(out) => out.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_attribute]
+pub fn constrainer(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let attr = TokenStream::from(attr);
+    if !attr.is_empty() {
+        return syn::Error::new_spanned(attr, "constrainer takes no arguments, options such as #[setters(all)] go on the struct").to_compile_error().into();
+    }
+    let item = parse_macro_input!(item as ItemStruct);
+    match ConstrainerInput::from_struct(item).and_then(expand) {
+        Ok(out) => out.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: ConstrainerInput) -> syn::Result<TokenStream> {
+    let name = input.name;
+
+    let mut declarations = Vec::new();
+    let mut opgensets = Vec::new();
+    let mut docs: BTreeMap<Ident, Vec<Attribute>> = BTreeMap::new();
+    let mut unnamed_checks = 0;
+
+    // Equations of `solve` items depend on whatever they name.
+    let mut value_names = BTreeSet::new();
+    for item in &input.items {
+        match item {
+            Item::Dynamic(item) => { value_names.insert(item.name.to_string()); },
+            Item::Constrained(item) => { value_names.insert(item.name.to_string()); },
+            Item::External(item) => { value_names.insert(item.name.to_string()); },
+            Item::State(item) => { value_names.insert(item.name.to_string()); },
+            Item::Solve(item) => value_names.extend(item.unknowns.iter().map(|unknown| unknown.name.to_string())),
+            Item::Sketch(item) => value_names.extend(item.primitives.iter().map(|primitive| primitive.name().to_string())),
+            Item::Check(_) | Item::Listener(_) | Item::OpGenSet(_) => {},
+        }
+    }
+
+    for item in input.items {
+        match item {
+            Item::Dynamic(item) => {
+                if let Some(predicate) = item.predicate {
+                    let check_name = Ident::new(&format!("__where_{}", item.name), item.name.span());
+                    let check_fn_name = Ident::new(&format!("check_where_{}", item.name), Span::call_site());
+                    declarations.push((check_name, Identifier::Check(Check {
+                        params: vec![Param {
+                            name: item.name.clone(),
+                            passing: Passing::Ref,
+                        }],
+                        block: predicate,
+                        check_fn_name,
+                        description: item.name.to_string(),
+                        dependents: BTreeSet::new(),
+                    })));
+                }
+                docs.insert(item.name.clone(), item.docs);
+                let normalize = item.normalize.map(|block| Normalize {
+                    normalize_fn_name: Ident::new(&format!("normalize_{}", item.name), Span::call_site()),
+                    block,
+                });
+                declarations.push((item.name, Identifier::Dynamic(Dynamic {
+                    ty: item.ty,
+                    normalize,
+                    dependents: BTreeSet::new(),
+                })));
+            },
+            Item::Constrained(item) => {
+                docs.insert(item.name.clone(), item.docs);
+                let compute_fn_name = Ident::new(&format!("compute_{}", item.name), Span::call_site());
+                let inverse = item.inverse.map(|assignments| Inverse {
+                    invert_fn_name: Ident::new(&format!("invert_{}", item.name), Span::call_site()),
+                    assignments,
+                });
+                declarations.push((item.name, Identifier::Constrained(Constrained {
+                    ty: item.ty,
+                    lazy: item.lazy,
+                    params: item.params,
+                    fallible: item.fallible,
+                    block: item.block,
+                    compute_fn_name,
+                    inverse,
+                    solve: None,
+                    dependents: BTreeSet::new(),
+                })));
+            },
+            Item::External(item) => {
+                declarations.push((item.name, Identifier::External(External {
+                    ty: item.ty,
+                    dependents: BTreeSet::new(),
+                })));
+            },
+            Item::State(item) => {
+                docs.insert(item.name.clone(), item.docs);
+                declarations.push((item.name, Identifier::State(State {
+                    ty: item.ty,
+                    dependents: BTreeSet::new(),
+                })));
+            },
+            Item::Check(item) => {
+                let (check_name, check_fn_name, description) = match item.name {
+                    Some(name) => {
+                        let check_fn_name = Ident::new(&format!("check_{}", name), Span::call_site());
+                        let description = name.to_string();
+                        (name, check_fn_name, description)
+                    },
+                    None => {
+                        unnamed_checks += 1;
+                        let params: Vec<String> = item.params.iter().map(|param| param.name.to_string()).collect();
+                        (
+                            Ident::new(&format!("__check_{}", unnamed_checks), item.keyword.span),
+                            Ident::new(&format!("check_{}", unnamed_checks), Span::call_site()),
+                            format!("({})", params.join(", ")),
+                        )
+                    },
+                };
+                declarations.push((check_name, Identifier::Check(Check {
+                    params: item.params,
+                    block: item.block,
+                    check_fn_name,
+                    description,
+                    dependents: BTreeSet::new(),
+                })));
+            },
+            Item::Listener(item) => {
+                declarations.push((item.name, Identifier::Listener(Listener {
+                    params: item.params,
+                    block: item.block,
+                    dependents: BTreeSet::new(),
+                })));
+            },
+            Item::Solve(item) => {
+                let unknown_names: Vec<String> = item.unknowns.iter().map(|unknown| unknown.name.to_string()).collect();
+                let solution_name = Ident::new(&format!("__solve_{}", unknown_names.join("_")), item.keyword.span);
+                let mut params: Vec<Param> = Vec::new();
+                for residual in &item.residuals {
+                    for name in referenced_names(residual.clone(), &value_names) {
+                        if !unknown_names.contains(&name.to_string()) && !params.iter().any(|param| param.name == name) {
+                            params.push(Param {
+                                name,
+                                passing: Passing::Ref,
+                            });
+                        }
+                    }
+                }
+                let count = item.unknowns.len();
+                declarations.push((solution_name.clone(), Identifier::Constrained(Constrained {
+                    ty: parse_quote! { [f64; #count] },
+                    lazy: false,
+                    params,
+                    fallible: true,
+                    block: TokenStream::new(),
+                    compute_fn_name: Ident::new(&format!("solve_{}", unknown_names.join("_")), Span::call_site()),
+                    inverse: None,
+                    solve: Some(Box::new(Solve {
+                        unknowns: item.unknowns.iter().map(|unknown| unknown.name.clone()).collect(),
+                        unknown_names: unknown_names.clone(),
+                        equation_names: item.equations,
+                        guesses: item.unknowns.iter().map(|unknown| unknown.guess.clone().unwrap_or_else(|| quote! { 1.0 })).collect(),
+                        bindings: TokenStream::new(),
+                        residuals: item.residuals,
+                        residuals_fn_name: Ident::new(&format!("residuals_{}", unknown_names.join("_")), Span::call_site()),
+                        description: format!("({})", unknown_names.join(", ")),
+                    })),
+                    dependents: BTreeSet::new(),
+                })));
+                for (position, unknown) in item.unknowns.into_iter().enumerate() {
+                    let compute_fn_name = Ident::new(&format!("compute_{}", unknown.name), Span::call_site());
+                    declarations.push((unknown.name, Identifier::Constrained(Constrained {
+                        ty: parse_quote! { f64 },
+                        lazy: false,
+                        params: vec![Param {
+                            name: solution_name.clone(),
+                            passing: Passing::Ref,
+                        }],
+                        fallible: false,
+                        block: quote! { #solution_name[#position] },
+                        compute_fn_name,
+                        inverse: None,
+                        solve: None,
+                        dependents: BTreeSet::new(),
+                    })));
+                }
+            },
+            Item::Sketch(item) => declarations.extend(sketch(item, &value_names)?),
+            Item::OpGenSet(item) => opgensets.push(item),
+        }
+    }
+
+    let setter_dynamics: Vec<&Ident> = opgensets.iter()
+        .flat_map(|opgenset| &opgenset.dynamics)
+        .chain(input.setters.groups.iter().flatten())
+        .collect();
+    let graph = Graph::new(declarations, &setter_dynamics)?;
+    let identifiers = &graph.identifiers;
+
+    let mut dynamic_fields = TokenStream::new();
+    let mut constrained_fields = TokenStream::new();
+    let mut external_fields = TokenStream::new();
+    let mut state_fields = TokenStream::new();
+    let mut state_args = TokenStream::new();
+    let mut deliminated_states = TokenStream::new();
+    let mut deliminated_dynamics = TokenStream::new();
+    let mut deliminated_constraineds = TokenStream::new();
+    let mut init_normalize = TokenStream::new();
+    let mut init_checks = TokenStream::new();
+    let mut init_constraineds = TokenStream::new();
+    let mut init_listeners = TokenStream::new();
+    let mut ops: TokenStream = TokenStream::new();
+    let mut dof_reports = Vec::new();
+    let mut structural_dofs = Vec::new();
+    let mut dof_external_args = BTreeMap::new();
+
+    for (name, identifier) in identifiers {
+        match identifier {
+            Identifier::Dynamic(Dynamic { ty, normalize, .. }) => {
+                let get_fn_name = Ident::new(&format!("get_{}", name), Span::call_site());
+                let docs = docs.get(name).into_iter().flatten();
+                ops.append_all(quote! {
+                    #(#docs)*
+                    pub fn #get_fn_name(&self) -> &#ty {
+                        &self.#name
+                    }
+                });
+                if let Some(Normalize { normalize_fn_name, block }) = normalize {
+                    ops.append_all(quote! { fn #normalize_fn_name (#name: #ty) -> #ty { #block }});
+                    init_normalize.append_all(quote! {
+                        let #name = Self::#normalize_fn_name(#name);
+                    });
+                }
+                dynamic_fields.append_all(quote! {
+                    #name: #ty,
+                });
+                deliminated_dynamics.append_all(quote! {
+                    #name,
+                });
+            },
+            Identifier::Constrained(constrained) => {
+                let ty = &constrained.ty;
+                let mut compute_args = TokenStream::new();
+                for param in &constrained.params {
+                    let param_name = &param.name;
+                    let param_ty = param_ty(identifiers[param_name].ty().unwrap(), param.passing);
+                    compute_args.append_all(quote! {
+                        #param_name: #param_ty,
+                    });
+                }
+
+                let compute_fn_name = &constrained.compute_fn_name;
+                let get_fn_name = Ident::new(&format!("get_{}", name), Span::call_site());
+                if let Some(solve) = constrained.solve.as_deref() {
+                    let Solve { unknowns, bindings, residuals, residuals_fn_name, .. } = solve;
+                    let positions = 0..unknowns.len();
+                    let equations = residuals.len();
+                    let residual_positions = 0..equations;
+                    let param_names: Vec<&Ident> = constrained.params.iter().map(|param| &param.name).collect();
+                    ops.append_all(quote! {
+                        #[allow(clippy::ptr_arg, unused_variables)]
+                        fn #residuals_fn_name (__unknowns: &[f64], __residuals: &mut [f64], #compute_args) {
+                            #(let #unknowns = __unknowns[#positions];)*
+                            #bindings
+                            #(__residuals[#residual_positions] = #residuals;)*
+                        }
+
+                        #[allow(clippy::ptr_arg)]
+                        fn #compute_fn_name (__previous: &#ty, #compute_args) -> ::core::result::Result<#ty, ::constrainer::SolveError> {
+                            let mut __solution = *__previous;
+                            ::constrainer::solve(&mut __solution, #equations, |__unknowns, __residuals| {
+                                Self::#residuals_fn_name(__unknowns, __residuals, #(#param_names,)*)
+                            })?;
+                            ::core::result::Result::Ok(__solution)
+                        }
+                    });
+                    dof_reports.push(dof_report(&graph, name, constrained, solve, &mut dof_external_args));
+                    let description = &solve.description;
+                    let structural_dof = solve.unknown_names.len() as isize - solve.equation_names.len() as isize;
+                    structural_dofs.push(quote! { (#description, #structural_dof) });
+                    constrained_fields.append_all(quote! {
+                        #name: #ty,
+                    });
+                    deliminated_constraineds.append_all(quote! {
+                        #name,
+                    });
+                    continue;
+                }
+                if constrained.lazy {
+                    let mut get_args = TokenStream::new();
+                    for param in &constrained.params {
+                        let arg = pass(place(&graph, &quote! { self }, &quote! { Self }, &param.name), param.passing);
+                        get_args.append_all(quote! {
+                            #arg,
+                        });
+                    }
+                    let docs = docs.get(name).into_iter().flatten();
+                    ops.append_all(quote! {
+                        #(#docs)*
+                        pub fn #get_fn_name(&self) -> &#ty {
+                            self.#name.get_or_init(|| Self::#compute_fn_name(#get_args))
+                        }
+                    });
+                    constrained_fields.append_all(quote! {
+                        #name: ::core::cell::OnceCell<#ty>,
+                    });
+                } else {
+                    let docs = docs.get(name).into_iter().flatten();
+                    ops.append_all(quote! {
+                        #(#docs)*
+                        pub fn #get_fn_name(&self) -> &#ty {
+                            &self.#name
+                        }
+                    });
+                    constrained_fields.append_all(quote! {
+                        #name: #ty,
+                    });
+                }
+                deliminated_constraineds.append_all(quote! {
+                    #name,
+                });
+                let block = &constrained.block;
+                // Params are passed as `&T` whatever `T` is, even when `&[U]` or `&str` would do.
+                let output = if constrained.fallible {
+                    quote! { ::core::result::Result<#ty, impl ::core::convert::Into<::constrainer::BoxError>> }
+                } else {
+                    quote! { #ty }
+                };
+                ops.append_all(quote! { #[allow(clippy::ptr_arg)] fn #compute_fn_name (#compute_args) -> #output { #block }});
+            },
+            Identifier::External(External { ty, .. }) => {
+                external_fields.append_all(quote! {
+                    #name: #ty,
+                });
+            },
+            Identifier::State(State { ty, .. }) => {
+                let get_fn_name = Ident::new(&format!("get_{}", name), Span::call_site());
+                let get_mut_fn_name = Ident::new(&format!("get_{}_mut", name), Span::call_site());
+                let docs = docs.get(name).map(Vec::as_slice).unwrap_or_default();
+                ops.append_all(quote! {
+                    #(#docs)*
+                    pub fn #get_fn_name(&self) -> &#ty {
+                        &self.#name
+                    }
+
+                    #(#docs)*
+                    pub fn #get_mut_fn_name(&mut self) -> &mut #ty {
+                        &mut self.#name
+                    }
+                });
+                state_fields.append_all(quote! {
+                    #name: #ty,
+                });
+                // Listeners called by `new` may modify it before it is stored.
+                let taken_mut = identifier.dependents().iter()
+                    .flat_map(|dependent| identifiers[*dependent].params())
+                    .any(|param| param.name == *name && param.passing == Passing::Mut);
+                if taken_mut {
+                    state_args.append_all(quote! {
+                        mut #name: #ty,
+                    });
+                } else {
+                    state_args.append_all(quote! {
+                        #name: #ty,
+                    });
+                }
+                deliminated_states.append_all(quote! {
+                    #name,
+                });
+            },
+            Identifier::Check(check) => {
+                let mut check_args = TokenStream::new();
+                for param in &check.params {
+                    let param_name = &param.name;
+                    let param_ty = param_ty(identifiers[param_name].ty().unwrap(), param.passing);
+                    check_args.append_all(quote! {
+                        #param_name: #param_ty,
+                    });
+                }
+                let check_fn_name = &check.check_fn_name;
+                let block = &check.block;
+                ops.append_all(quote! { #[allow(clippy::ptr_arg)] fn #check_fn_name (#check_args) -> bool { #block }});
+            },
+            Identifier::Listener(listener) => {
+                let mut listener_args = TokenStream::new();
+                for param in &listener.params {
+                    let param_name = &param.name;
+                    let param_ty = param_ty(identifiers[param_name].ty().unwrap(), param.passing);
+                    listener_args.append_all(quote! {
+                        #param_name: #param_ty,
+                    });
+                }
+                let block = &listener.block;
+                ops.append_all(quote! { #[allow(clippy::ptr_arg)] fn #name (#listener_args) { #block }});
+            },
+        }
+    }
+
+    for index in &graph.order {
+        let (name, identifier) = identifiers.get_index(*index).unwrap();
+        let mut init_args = TokenStream::new();
+        for param in identifier.params() {
+            let arg = pass(init_place(&graph, &param.name), param.passing);
+            init_args.append_all(quote! {
+                #arg,
+            });
+        }
+        match identifier {
+            Identifier::Constrained(constrained) if constrained.lazy => {
+                init_constraineds.append_all(quote! {
+                    let #name = ::core::cell::OnceCell::new();
+                });
+            },
+            Identifier::Constrained(constrained) => {
+                // Solved for the first time from the guesses.
+                if let Some(Solve { guesses, .. }) = constrained.solve.as_deref() {
+                    init_args = quote! { &[#(#guesses,)*], #init_args };
+                }
+                let compute = compute(&quote! { Self }, name, constrained, &init_args, &[]);
+                init_constraineds.append_all(quote! {
+                    let #name = #compute;
+                });
+            },
+            // Before anything is computed from what they check.
+            Identifier::Check(check) => {
+                init_checks.append_all(verify(&quote! { Self }, check, &init_args));
+            },
+            // Only called once everything is computed, so that nothing is called if that fails.
+            Identifier::Listener(_) => {
+                init_listeners.append_all(quote! {
+                    Self::#name(#init_args);
+                });
+            },
+            Identifier::Dynamic(_) | Identifier::External(_) | Identifier::State(_) => {},
+        }
+    }
+
+    if !dof_reports.is_empty() {
+        let dof_external_args = dof_external_args.into_values();
+        ops.append_all(quote! {
+            pub const STRUCTURAL_DOF: &'static [(&'static str, isize)] = &[#(#structural_dofs),*];
+
+            pub fn dof_report(&self, #(#dof_external_args)*) -> ::std::vec::Vec<::constrainer::DofReport> {
+                ::std::vec![#(#dof_reports,)*]
+            }
+        });
+    }
+
+    if input.modes.gradients {
+        ops.append_all(gradients(&graph));
+    }
+    let mut interval_view_items = TokenStream::new();
+    if input.modes.intervals {
+        let interval_view = interval_view(&graph, &input.vis, &name);
+        ops.append_all(interval_view.ops);
+        interval_view_items = interval_view.items;
+    }
+
+    let mut setters: Vec<BTreeSet<usize>> = Vec::new();
+    let explicit_setters = opgensets.iter()
+        .map(|opgenset| &opgenset.dynamics)
+        .chain(&input.setters.groups);
+    for dynamics in explicit_setters {
+        let set_dynamics = dynamics.iter().map(|dynamic| identifiers.get_index_of(dynamic).unwrap()).collect();
+        setters.push(set_dynamics);
+    }
+    if input.setters.all {
+        for (index, identifier) in identifiers.values().enumerate() {
+            if let Identifier::Dynamic(_) = identifier {
+                setters.push(BTreeSet::from([index]));
+            }
+        }
+    }
+    let mut generated_setters = BTreeSet::new();
+    for set_dynamics in setters {
+        if generated_setters.insert(set_dynamics.clone()) {
+            ops.append_all(setter(&graph, &name, &set_dynamics, input.modes));
+        }
+    }
+    for (constrained_name, identifier) in identifiers {
+        if let Identifier::Constrained(constrained @ Constrained { inverse: Some(_), .. }) = identifier {
+            ops.append_all(inverse_setter(&graph, &name, constrained_name, constrained, input.modes));
+        }
+    }
+
+    let attrs = input.attrs;
+    let vis = input.vis;
+    let generics = input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Generic parameters only used by externals would otherwise be rejected as unused.
+    let mut marker_field = TokenStream::new();
+    let mut deliminated_marker = TokenStream::new();
+    if generics.lifetimes().next().is_some() || generics.type_params().next().is_some() {
+        let lifetimes = generics.lifetimes().map(|def| &def.lifetime);
+        let type_params = generics.type_params().map(|param| &param.ident);
+        marker_field.append_all(quote! {
+            __constrainer_marker: ::core::marker::PhantomData<fn() -> (#(&#lifetimes (),)* #(#type_params,)*)>,
+        });
+        deliminated_marker.append_all(quote! {
+            __constrainer_marker: ::core::marker::PhantomData,
+        });
+    }
+
+    let transaction = transaction(&graph, &vis, &name, &generics, input.modes);
+    let transaction_items = transaction.items;
+    let transaction_ops = transaction.ops;
+
+    let subscriptions = if input.modes.subscriptions {
+        subscriptions(&graph)
+    } else {
+        Subscriptions {
+            fields: TokenStream::new(),
+            deliminated_fields: TokenStream::new(),
+            ops: TokenStream::new(),
+        }
+    };
+    let subscription_fields = subscriptions.fields;
+    let deliminated_subscriptions = subscriptions.deliminated_fields;
+    let subscription_ops = subscriptions.ops;
+
+    let changed_struct = changed_struct(&graph, &vis, &name);
+
+    let new_output = output(&graph, quote! { Self });
+    let new_value = output_value(&graph, quote! {
+        Self {
+            #deliminated_dynamics
+            #deliminated_constraineds
+            #deliminated_states
+            #deliminated_subscriptions
+            #deliminated_marker
+        }
+    });
+
+    let debug_impl = if input.debug {
+        debug_impl(&graph, &name, &generics)
+    } else {
+        TokenStream::new()
+    };
+
+    let mut out = TokenStream::new();
+
+    out.append_all(quote! {
+        #(#attrs)*
+        #vis struct #name #generics #where_clause {
+            #dynamic_fields
+            #constrained_fields
+            #state_fields
+            #subscription_fields
+            #marker_field
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            pub fn new ( #dynamic_fields #state_args #external_fields ) -> #new_output {
+                #init_normalize
+                #init_checks
+                #init_constraineds
+                #init_listeners
+
+                #new_value
+            }
+
+            #ops
+            #transaction_ops
+            #subscription_ops
+        }
+
+        #debug_impl
+        #transaction_items
+        #changed_struct
+        #interval_view_items
+    });
+
+    Ok(out)
+}
+
+// A place expression for a dynamic, constrained or external inside `new`, where everything
+// is still a local. Lazy constraineds are only computed there if something else needs them.
+fn init_place(graph: &Graph, param: &Ident) -> TokenStream {
+    match &graph.identifiers[param] {
+        Identifier::Constrained(constrained) if constrained.lazy => {
+            let compute_fn_name = &constrained.compute_fn_name;
+            let args = constrained.params.iter().map(|param| pass(init_place(graph, &param.name), param.passing));
+            quote! { (*#param.get_or_init(|| Self::#compute_fn_name(#(#args,)*))) }
+        },
+        _ => quote! { #param },
+    }
+}
+
+// Every name of `names` used as a value in `tokens`, in order. Field and method names after a
+// `.` are not values.
+fn referenced_names(tokens: TokenStream, names: &BTreeSet<String>) -> Vec<Ident> {
+    let mut referenced = Vec::new();
+    let mut after_dot = false;
+    for tt in tokens {
+        match &tt {
+            TokenTree::Ident(ident) if !after_dot && names.contains(&ident.to_string()) => referenced.push(ident.clone()),
+            TokenTree::Group(group) => referenced.extend(referenced_names(group.stream(), names)),
+            _ => {},
+        }
+        after_dot = matches!(&tt, TokenTree::Punct(punct) if punct.as_char() == '.');
+    }
+    referenced
+}
+
+// The report of a `solve` item or sketch at its current solution, for `dof_report`. Externals
+// its equations read are added to `external_args`.
+fn dof_report(graph: &Graph, name: &Ident, constrained: &Constrained, solve: &Solve, external_args: &mut BTreeMap<usize, TokenStream>) -> TokenStream {
+    let mut args = TokenStream::new();
+    for param in &constrained.params {
+        let param_name = &param.name;
+        let (index, _, identifier) = graph.identifiers.get_full(param_name).unwrap();
+        let arg = match identifier {
+            Identifier::External(External { ty, .. }) => {
+                external_args.insert(index, quote! {
+                    #param_name: #ty,
+                });
+                pass(quote! { #param_name }, param.passing)
+            },
+            _ => pass(place(graph, &quote! { self }, &quote! { Self }, param_name), param.passing),
+        };
+        args.append_all(quote! {
+            #arg,
+        });
+    }
+    let Solve { unknown_names, equation_names, residuals_fn_name, description, .. } = solve;
+    quote! {
+        ::constrainer::DofReport::new(
+            #description,
+            &[#(#unknown_names),*],
+            &[#(#equation_names),*],
+            &self.#name,
+            |__unknowns, __residuals| Self::#residuals_fn_name(__unknowns, __residuals, #args),
+        )
+    }
+}
+
+// Like a derived `Debug`, but leaving out the fields added for subscriptions, generics and
+// `solve` items.
+fn debug_impl(graph: &Graph, name: &Ident, generics: &Generics) -> TokenStream {
+    // In the order of the fields of the struct.
+    let mut fields: Vec<(usize, &Ident)> = graph.identifiers.iter()
+        .filter_map(|(field_name, identifier)| match identifier {
+            Identifier::Dynamic(_) => Some((0, field_name)),
+            Identifier::Constrained(_) if !identifier.is_hidden() => Some((1, field_name)),
+            Identifier::State(_) => Some((2, field_name)),
+            _ => None,
+        })
+        .collect();
+    fields.sort_by_key(|(group, _)| *group);
+    let fields: Vec<&Ident> = fields.into_iter().map(|(_, field_name)| field_name).collect();
+    let field_names = fields.iter().map(|field_name| field_name.to_string());
+    let name_str = name.to_string();
+
+    let mut debug_generics = generics.clone();
+    let type_params: Vec<Ident> = generics.type_params().map(|param| param.ident.clone()).collect();
+    let where_clause = debug_generics.make_where_clause();
+    for type_param in type_params {
+        where_clause.predicates.push(parse_quote!(#type_param: ::core::fmt::Debug));
+    }
+    let (impl_generics, ty_generics, where_clause) = debug_generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::core::fmt::Debug for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_struct(#name_str)
+                    #(.field(#field_names, &self.#fields))*
+                    .finish()
+            }
+        }
+    }
+}
+
+fn setter(graph: &Graph, constrainer_name: &Ident, set_dynamic_indices: &BTreeSet<usize>, modes: Modes) -> TokenStream {
+    let identifiers = &graph.identifiers;
+    let mut fn_name = String::from("set");
+    let mut set_fn_args = TokenStream::new();
+    for index in set_dynamic_indices {
+        let (name, identifier) = identifiers.get_index(*index).unwrap();
+        fn_name.push_str(&format!("_{}", name));
+        let ty = identifier.ty().unwrap();
+        set_fn_args.append_all(quote! {
+            #name: #ty,
+        });
+    }
+    let fn_name = Ident::new(&fn_name, Span::call_site());
+    set_fn(graph, constrainer_name, &fn_name, set_fn_args, TokenStream::new(), set_dynamic_indices, modes)
+}
+
+// A setter of the set dynamics, whose new values are locals named after them once `prelude`
+// ran. `set_fn_args` are the args other than `self` and externals.
+fn set_fn(
+    graph: &Graph,
+    constrainer_name: &Ident,
+    fn_name: &Ident,
+    mut set_fn_args: TokenStream,
+    prelude: TokenStream,
+    set_dynamic_indices: &BTreeSet<usize>,
+    modes: Modes,
+) -> TokenStream {
+    let identifiers = &graph.identifiers;
+    let mut set_dynamics = BTreeMap::new();
+    for index in set_dynamic_indices {
+        let (name, identifier) = identifiers.get_index(*index).unwrap();
+        let dynamic = if let Identifier::Dynamic(dynamic) = identifier {
+            dynamic
+        } else {
+            unreachable!()
+        };
+        set_dynamics.insert(*index, (name, dynamic));
+    }
+
+    let mut fn_block = prelude;
+    for (name, dynamic) in set_dynamics.values() {
+        if let Some(Normalize { normalize_fn_name, .. }) = &dynamic.normalize {
+            fn_block.append_all(quote! {
+                let #name = Self::#normalize_fn_name(#name);
+            });
+        }
+    }
+    // Checks see the new values of the dynamics being set before any of them is stored.
+    let checks: BTreeSet<usize> = set_dynamics.values()
+        .flat_map(|(_, dynamic)| &dynamic.dependents)
+        .copied()
+        .filter(|index| matches!(identifiers[*index], Identifier::Check(_)))
+        .collect();
+    for index in checks {
+        let check = if let Identifier::Check(check) = &identifiers[index] {
+            check
+        } else {
+            unreachable!()
+        };
+        let mut args = TokenStream::new();
+        for param in &check.params {
+            let param_name = &param.name;
+            let place = if set_dynamics.values().any(|(name, _)| *name == param_name) {
+                quote! { #param_name }
+            } else {
+                quote! { self.#param_name }
+            };
+            let arg = pass(place, param.passing);
+            args.append_all(quote! {
+                #arg,
+            });
+        }
+        fn_block.append_all(verify(&quote! { Self }, check, &args));
+    }
+    for (index, (name, _)) in &set_dynamics {
+        if graph.keeps_old(*index) {
+            let old = old_value(name);
+            fn_block.append_all(quote! {
+                let #old = ::core::option::Option::Some(::core::mem::replace(&mut self.#name, #name));
+            });
+        } else {
+            fn_block.append_all(quote! {
+                self.#name = #name;
+            });
+        }
+    }
+
+    let sources = set_dynamics.keys().map(|index| (*index, Dirty::Always)).collect();
+    let propagation = propagate(graph, &sources, modes, &quote! { self }, &quote! { Self });
+    fn_block.append_all(propagation.block);
+    for set_fn_external_arg in propagation.external_args.into_values() {
+        set_fn_args.append_all(set_fn_external_arg);
+    }
+
+    let set_fn_output = output(graph, changed_name(constrainer_name).into_token_stream());
+    let changed = output_value(graph, changed_value(graph, constrainer_name, &propagation.changed));
+
+    quote! {
+        pub fn #fn_name(&mut self, #set_fn_args) -> #set_fn_output {
+            #fn_block
+            #changed
+        }
+    }
+}
+
+// `set_<constrained>`, setting the dynamics computed by the inverse of the constrained.
+fn inverse_setter(graph: &Graph, constrainer_name: &Ident, name: &Ident, constrained: &Constrained, modes: Modes) -> TokenStream {
+    let identifiers = &graph.identifiers;
+    let inverse = constrained.inverse.as_ref().unwrap();
+    let invert_fn_name = &inverse.invert_fn_name;
+    let ty = &constrained.ty;
+
+    let mut invert_args = quote! { #name: &#ty, };
+    let mut args = quote! { &#name, };
+    for param in &constrained.params {
+        let param_name = &param.name;
+        if inverse.assignments.iter().any(|assignment| assignment.dynamic == *param_name) {
+            continue;
+        }
+        let param_identifier = &identifiers[param_name];
+        let param_ty = param_ty(param_identifier.ty().unwrap(), param.passing);
+        invert_args.append_all(quote! {
+            #param_name: #param_ty,
+        });
+        // Externals are args of the setter, which needs them to recompute the constrained.
+        let arg = match param_identifier {
+            Identifier::External(_) => pass(quote! { #param_name }, param.passing),
+            _ => pass(place(graph, &quote! { self }, &quote! { Self }, param_name), param.passing),
+        };
+        args.append_all(quote! {
+            #arg,
+        });
+    }
+    let dynamics: Vec<&Ident> = inverse.assignments.iter().map(|assignment| &assignment.dynamic).collect();
+    let dynamic_tys = dynamics.iter().map(|dynamic| identifiers[*dynamic].ty().unwrap());
+    let values = inverse.assignments.iter().map(|assignment| &assignment.value);
+
+    let set_fn_name = Ident::new(&format!("set_{}", name), Span::call_site());
+    let set_dynamics = dynamics.iter().map(|dynamic| identifiers.get_index_of(*dynamic).unwrap()).collect();
+    let set_fn = set_fn(
+        graph,
+        constrainer_name,
+        &set_fn_name,
+        quote! { #name: #ty, },
+        quote! {
+            let (#(#dynamics,)*) = Self::#invert_fn_name(#args);
+        },
+        &set_dynamics,
+        modes,
+    );
+
+    // Every param not computed by the inverse is passed, whether the inverse needs it or not.
+    quote! {
+        #[allow(clippy::ptr_arg, unused_variables)]
+        fn #invert_fn_name(#invert_args) -> (#(#dynamic_tys,)*) {
+            (#(#values,)*)
+        }
+
+        #set_fn
+    }
+}
@@ -0,0 +1,79 @@
+use proc_macro2::{
+    Ident,
+    TokenStream,
+};
+use quote::{
+    TokenStreamExt,
+    quote,
+};
+use syn::Type;
+
+use std::collections::BTreeSet;
+
+use crate::graph::{
+    Constrained,
+    External,
+    Graph,
+    Identifier,
+};
+use crate::propagate::param_ty;
+
+fn is_f64(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("f64"))
+}
+
+pub fn is_f64_dynamic(identifier: &Identifier) -> bool {
+    matches!(identifier, Identifier::Dynamic(dynamic) if is_f64(&dynamic.ty))
+}
+
+// The constraineds whose bodies can be evaluated over a type standing in for `f64`, such as
+// `Dual` or `Interval`: infallible `f64` ones whose dynamic and constrained params are all
+// `f64` dynamics or such constraineds themselves. Externals are constants.
+pub fn numeric_constraineds(graph: &Graph) -> BTreeSet<usize> {
+    let mut numeric = BTreeSet::new();
+    for index in &graph.order {
+        let constrained = match &graph.identifiers[*index] {
+            Identifier::Constrained(constrained @ Constrained { fallible: false, solve: None, .. }) => constrained,
+            _ => continue,
+        };
+        if !is_f64(&constrained.ty) {
+            continue;
+        }
+        let params_numeric = constrained.params.iter().all(|param| {
+            let (param_index, _, param_identifier) = graph.identifiers.get_full(&param.name).unwrap();
+            match param_identifier {
+                Identifier::Dynamic(_) => is_f64_dynamic(param_identifier),
+                Identifier::Constrained(_) => numeric.contains(&param_index),
+                _ => true,
+            }
+        });
+        if params_numeric {
+            numeric.insert(*index);
+        }
+    }
+    numeric
+}
+
+// The body of a numeric constrained as a function over `number` instead of `f64`. Results that
+// are plain `f64`s, like literals, are converted.
+pub fn numeric_fn(graph: &Graph, constrained: &Constrained, fn_name: &Ident, number: &Type) -> TokenStream {
+    let mut args = TokenStream::new();
+    for param in &constrained.params {
+        let param_name = &param.name;
+        let ty = match &graph.identifiers[param_name] {
+            Identifier::External(External { ty, .. }) => ty,
+            _ => number,
+        };
+        let param_ty = param_ty(ty, param.passing);
+        args.append_all(quote! {
+            #param_name: #param_ty,
+        });
+    }
+    let block = &constrained.block;
+    quote! {
+        #[allow(clippy::ptr_arg)]
+        fn #fn_name (#args) -> #number {
+            ::core::convert::Into::into({ #block })
+        }
+    }
+}
@@ -0,0 +1,951 @@
+use proc_macro2::{
+    Delimiter,
+    Ident,
+    Span,
+    Spacing,
+    TokenStream,
+    TokenTree,
+};
+use quote::{
+    format_ident,
+    quote,
+    ToTokens,
+};
+use syn::{
+    braced,
+    bracketed,
+    buffer::Cursor,
+    parenthesized,
+    parse_quote,
+    parse::{
+        Parse,
+        ParseStream,
+    },
+    punctuated::Punctuated,
+    Attribute,
+    Expr,
+    Field,
+    Fields,
+    Generics,
+    ItemStruct,
+    Path,
+    Token,
+    Type,
+    Visibility,
+};
+
+mod kw {
+    syn::custom_keyword!(dynamic);
+    syn::custom_keyword!(constrained);
+    syn::custom_keyword!(lazy);
+    syn::custom_keyword!(external);
+    syn::custom_keyword!(state);
+    syn::custom_keyword!(check);
+    syn::custom_keyword!(normalize);
+    syn::custom_keyword!(inverse);
+    syn::custom_keyword!(listener);
+    syn::custom_keyword!(solve);
+    syn::custom_keyword!(sketch);
+    syn::custom_keyword!(point);
+    syn::custom_keyword!(line);
+    syn::custom_keyword!(circle);
+    syn::custom_keyword!(opgenset);
+    syn::custom_keyword!(clone);
+    syn::custom_keyword!(changed);
+    syn::custom_keyword!(all);
+    syn::custom_keyword!(group);
+    syn::custom_keyword!(deps);
+    syn::custom_keyword!(with);
+    syn::custom_keyword!(fallible);
+}
+
+pub struct ConstrainerInput {
+    // Attributes other than the ones understood by the macro are forwarded to the struct.
+    pub attrs: Vec<Attribute>,
+    pub setters: SetterOptions,
+    pub modes: Modes,
+    // Whether `Debug` is implemented for the struct. Always with `create_constrainer!`, only
+    // when derived with `#[constrainer]`.
+    pub debug: bool,
+    // Of the struct and the other structs generated along with it.
+    pub vis: Visibility,
+    pub name: Ident,
+    pub generics: Generics,
+    pub items: Vec<Item>,
+}
+
+impl Parse for ConstrainerInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let (attrs, setters, modes, _) = parse_attrs(input.call(Attribute::parse_outer)?)?;
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        let mut generics: Generics = input.parse()?;
+        generics.where_clause = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut items = Vec::new();
+        while !content.is_empty() {
+            items.push(content.parse()?);
+        }
+        Ok(Self {
+            attrs,
+            setters,
+            modes,
+            debug: true,
+            vis,
+            name,
+            generics,
+            items,
+        })
+    }
+}
+
+impl ConstrainerInput {
+    // The `#[constrainer]` form: a plain struct whose fields are marked `#[dynamic]`,
+    // `#[constrained(...)]`, `#[external]` or `#[state]`.
+    pub fn from_struct(item: ItemStruct) -> syn::Result<Self> {
+        let (attrs, setters, modes, debug) = parse_attrs(item.attrs)?;
+        let fields = match item.fields {
+            Fields::Named(fields) => fields.named,
+            fields => return Err(syn::Error::new_spanned(fields, "A constrainer needs named fields")),
+        };
+        let mut items = Vec::new();
+        for field in fields {
+            items.push(field_item(field)?);
+        }
+        Ok(Self {
+            attrs,
+            setters,
+            modes,
+            debug,
+            vis: item.vis,
+            name: item.ident,
+            generics: item.generics,
+            items,
+        })
+    }
+}
+
+fn field_item(field: Field) -> syn::Result<Item> {
+    let name = field.ident.expect("named field");
+    if !matches!(field.vis, Visibility::Inherited) {
+        return Err(syn::Error::new_spanned(field.vis, "Fields are read through the generated getters and cannot be public"));
+    }
+    let mut item = None;
+    let mut docs = Vec::new();
+    for attr in field.attrs {
+        if attr.path.is_ident("doc") {
+            docs.push(attr);
+            continue;
+        }
+        let ty = field.ty.clone();
+        let parsed = if attr.path.is_ident("dynamic") {
+            no_arguments(&attr)?;
+            Item::Dynamic(DynamicItem {
+                docs: Vec::new(),
+                name: name.clone(),
+                ty,
+                normalize: None,
+                predicate: None,
+            })
+        } else if attr.path.is_ident("external") {
+            no_arguments(&attr)?;
+            Item::External(ExternalItem {
+                name: name.clone(),
+                ty,
+            })
+        } else if attr.path.is_ident("state") {
+            no_arguments(&attr)?;
+            Item::State(StateItem {
+                docs: Vec::new(),
+                name: name.clone(),
+                ty,
+            })
+        } else if attr.path.is_ident("constrained") {
+            let options = if attr.tokens.is_empty() {
+                ConstrainedOptions::default()
+            } else {
+                attr.parse_args()?
+            };
+            Item::Constrained(options.item(name.clone(), ty))
+        } else {
+            return Err(syn::Error::new_spanned(attr, "Expected #[dynamic], #[constrained], #[external] or #[state]"));
+        };
+        if item.replace(parsed).is_some() {
+            return Err(syn::Error::new_spanned(attr.path, "A field is either a dynamic, a constrained, an external or state"));
+        }
+    }
+    let mut item = item.ok_or_else(|| syn::Error::new_spanned(name, "Expected #[dynamic], #[constrained], #[external] or #[state] on the field"))?;
+    match &mut item {
+        Item::Dynamic(dynamic) => dynamic.docs = docs,
+        Item::Constrained(constrained) => constrained.docs = docs,
+        Item::State(state) => state.docs = docs,
+        // Externals are only arguments, of `new` and setters, and have nothing to put docs on.
+        _ => {},
+    }
+    Ok(item)
+}
+
+fn lazy_fallible(span: Span) -> syn::Error {
+    syn::Error::new(span, "A lazy constrained cannot be fallible, its getter has nowhere to return the error.")
+}
+
+fn no_arguments(attr: &Attribute) -> syn::Result<()> {
+    if attr.tokens.is_empty() {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(&attr.tokens, format!("{} takes no arguments", attr.path.get_ident().unwrap())))
+    }
+}
+
+// The arguments of `#[constrained(...)]`: either the params alone, computed by `compute_<name>`,
+// or `deps = [params], with = path`, plus `lazy` and `fallible` in both cases.
+#[derive(Default)]
+struct ConstrainedOptions {
+    lazy: bool,
+    // Where `fallible` was given.
+    fallible: Option<Span>,
+    params: Option<Vec<Param>>,
+    with: Option<Path>,
+}
+
+impl ConstrainedOptions {
+    fn item(self, name: Ident, ty: Type) -> ConstrainedItem {
+        let with = self.with.unwrap_or_else(|| format_ident!("compute_{}", name).into());
+        let params = self.params.unwrap_or_default();
+        let args = params.iter().map(|param| &param.name);
+        ConstrainedItem {
+            docs: Vec::new(),
+            lazy: self.lazy,
+            name,
+            ty,
+            fallible: self.fallible.is_some(),
+            block: quote!(#with(#(#args),*)),
+            params,
+            inverse: None,
+        }
+    }
+}
+
+impl Parse for ConstrainedOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+        let mut bare_params = Vec::new();
+        while !input.is_empty() {
+            if input.peek(kw::lazy) {
+                input.parse::<kw::lazy>()?;
+                options.lazy = true;
+            } else if input.peek(kw::fallible) {
+                options.fallible = Some(input.parse::<kw::fallible>()?.span);
+            } else if input.peek(kw::deps) && input.peek2(Token![=]) {
+                let keyword = input.parse::<kw::deps>()?;
+                input.parse::<Token![=]>()?;
+                let content;
+                bracketed!(content in input);
+                let params = Punctuated::<Param, Token![,]>::parse_terminated(&content)?;
+                if options.params.replace(params.into_iter().collect()).is_some() {
+                    return Err(syn::Error::new(keyword.span, "deps given twice"));
+                }
+            } else if input.peek(kw::with) && input.peek2(Token![=]) {
+                let keyword = input.parse::<kw::with>()?;
+                input.parse::<Token![=]>()?;
+                if options.with.replace(input.parse()?).is_some() {
+                    return Err(syn::Error::new(keyword.span, "with given twice"));
+                }
+            } else {
+                bare_params.push(input.parse()?);
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        if let (true, Some(span)) = (options.lazy, options.fallible) {
+            return Err(lazy_fallible(span));
+        }
+        if !bare_params.is_empty() {
+            if options.params.is_some() {
+                return Err(input.error("Give the params either alone or as deps = [...], not both"));
+            }
+            options.params = Some(bare_params);
+        }
+        Ok(options)
+    }
+}
+
+// Splits the attributes understood by the macro from the ones forwarded to the struct. `Debug`
+// is taken out of derives and implemented by the macro, leaving out the fields it adds.
+fn parse_attrs(all_attrs: Vec<Attribute>) -> syn::Result<(Vec<Attribute>, SetterOptions, Modes, bool)> {
+    let mut attrs = Vec::new();
+    let mut setters = SetterOptions::default();
+    let mut modes = Modes::default();
+    let mut debug = false;
+    for attr in all_attrs {
+        if attr.path.is_ident("derive") {
+            let derives = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+            let count = derives.len();
+            let derives: Vec<Path> = derives.into_iter()
+                .filter(|derive| derive.segments.last().is_none_or(|segment| segment.ident != "Debug"))
+                .collect();
+            debug |= derives.len() != count;
+            if !derives.is_empty() {
+                attrs.push(parse_quote!(#[derive(#(#derives),*)]));
+            }
+        } else if attr.path.is_ident("setters") {
+            let options = attr.parse_args_with(Punctuated::<SetterOption, Token![,]>::parse_terminated)?;
+            for option in options {
+                match option {
+                    SetterOption::All => setters.all = true,
+                    SetterOption::Group(dynamics) => setters.groups.push(dynamics),
+                }
+            }
+        } else if let Some(mode) = modes.flag(&attr) {
+            if !attr.tokens.is_empty() {
+                return Err(syn::Error::new_spanned(&attr.tokens, format!("{} takes no arguments", attr.path.get_ident().unwrap())));
+            }
+            *mode = true;
+        } else {
+            attrs.push(attr);
+        }
+    }
+    Ok((attrs, setters, modes, debug))
+}
+
+// Switched on by attributes without arguments, e.g. `#[cutoff]`.
+#[derive(Clone, Copy, Default)]
+pub struct Modes {
+    // Whether constraineds stop propagating when their recomputed value is unchanged.
+    pub cutoff: bool,
+    // Whether closures can subscribe to dynamics and constraineds at runtime.
+    pub subscriptions: bool,
+    // Whether `f64` constraineds get derivatives with respect to the dynamics they depend on.
+    pub gradients: bool,
+    // Whether an `IntervalView` of the `f64` dynamics and constraineds is generated.
+    pub intervals: bool,
+}
+
+impl Modes {
+    fn flag(&mut self, attr: &Attribute) -> Option<&mut bool> {
+        if attr.path.is_ident("cutoff") {
+            Some(&mut self.cutoff)
+        } else if attr.path.is_ident("subscriptions") {
+            Some(&mut self.subscriptions)
+        } else if attr.path.is_ident("gradients") {
+            Some(&mut self.gradients)
+        } else if attr.path.is_ident("intervals") {
+            Some(&mut self.intervals)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SetterOptions {
+    pub all: bool,
+    pub groups: Vec<Vec<Ident>>,
+}
+
+enum SetterOption {
+    All,
+    Group(Vec<Ident>),
+}
+
+impl Parse for SetterOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(kw::all) {
+            input.parse::<kw::all>()?;
+            Ok(SetterOption::All)
+        } else if lookahead.peek(kw::group) {
+            let keyword = input.parse::<kw::group>()?;
+            let dynamics = parse_idents(input)?;
+            if dynamics.is_empty() {
+                return Err(syn::Error::new(keyword.span, "group needs at least one dynamic"));
+            }
+            Ok(SetterOption::Group(dynamics))
+        } else {
+            Err(lookahead.error())
+        }
+    }
+}
+
+pub enum Item {
+    Dynamic(DynamicItem),
+    Constrained(ConstrainedItem),
+    External(ExternalItem),
+    State(StateItem),
+    Check(CheckItem),
+    Listener(ListenerItem),
+    Solve(SolveItem),
+    Sketch(SketchItem),
+    OpGenSet(OpGenSetItem),
+}
+
+impl Parse for Item {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(kw::dynamic) {
+            input.parse().map(Item::Dynamic)
+        } else if lookahead.peek(kw::constrained) || lookahead.peek(kw::lazy) {
+            input.parse().map(Item::Constrained)
+        } else if lookahead.peek(kw::external) {
+            input.parse().map(Item::External)
+        } else if lookahead.peek(kw::state) {
+            input.parse().map(Item::State)
+        } else if lookahead.peek(kw::check) {
+            input.parse().map(Item::Check)
+        } else if lookahead.peek(kw::listener) {
+            input.parse().map(Item::Listener)
+        } else if lookahead.peek(kw::solve) {
+            input.parse().map(Item::Solve)
+        } else if lookahead.peek(kw::sketch) {
+            input.parse().map(Item::Sketch)
+        } else if lookahead.peek(kw::opgenset) {
+            input.parse().map(Item::OpGenSet)
+        } else {
+            Err(lookahead.error())
+        }
+    }
+}
+
+pub struct DynamicItem {
+    // Put on the field and getter. Only the `#[constrainer]` form has them.
+    pub docs: Vec<Attribute>,
+    pub name: Ident,
+    pub ty: Type,
+    // `normalize { ... }`, replacing every value given for the dynamic by its result.
+    pub normalize: Option<TokenStream>,
+    // `where predicate`, a `bool` expression the dynamic has to satisfy.
+    pub predicate: Option<TokenStream>,
+}
+
+impl Parse for DynamicItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<kw::dynamic>()?;
+        let name = input.parse()?;
+        let ty = input.parse()?;
+        let normalize = if input.parse::<Option<kw::normalize>>()?.is_some() {
+            Some(parse_block(input)?)
+        } else {
+            None
+        };
+        let predicate = if input.parse::<Option<Token![where]>>()?.is_some() {
+            Some(input.parse::<Expr>()?.into_token_stream())
+        } else {
+            None
+        };
+        Ok(Self {
+            docs: Vec::new(),
+            name,
+            ty,
+            normalize,
+            predicate,
+        })
+    }
+}
+
+pub struct ConstrainedItem {
+    pub docs: Vec<Attribute>,
+    pub lazy: bool,
+    pub name: Ident,
+    pub ty: Type,
+    pub params: Vec<Param>,
+    // `try`: the block returns a `Result` of the type.
+    pub fallible: bool,
+    pub block: TokenStream,
+    // `inverse { x = expr; }`, the values of dynamics for which the block returns a given value.
+    pub inverse: Option<Vec<InverseAssignment>>,
+}
+
+impl Parse for ConstrainedItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lazy = input.parse::<Option<kw::lazy>>()?.is_some();
+        input.parse::<kw::constrained>()?;
+        let name = input.parse()?;
+        let ty = parse_type_before_params(input)?;
+        let params = parse_params(input)?;
+        let fallible = match input.parse::<Option<Token![try]>>()? {
+            Some(keyword) if lazy => return Err(lazy_fallible(keyword.span)),
+            Some(_) => true,
+            None => false,
+        };
+        let block = parse_block(input)?;
+        let inverse = match input.parse::<Option<kw::inverse>>()? {
+            Some(keyword) => {
+                let content;
+                braced!(content in input);
+                let assignments = Punctuated::<InverseAssignment, Token![;]>::parse_terminated(&content)?;
+                if assignments.is_empty() {
+                    return Err(syn::Error::new(keyword.span, "inverse needs to set at least one dynamic"));
+                }
+                Some(assignments.into_iter().collect())
+            },
+            None => None,
+        };
+        Ok(Self {
+            docs: Vec::new(),
+            lazy,
+            name,
+            ty,
+            params,
+            fallible,
+            block,
+            inverse,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct InverseAssignment {
+    pub dynamic: Ident,
+    pub value: TokenStream,
+}
+
+impl Parse for InverseAssignment {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let dynamic = input.parse()?;
+        input.parse::<Token![=]>()?;
+        Ok(Self {
+            dynamic,
+            value: input.parse::<Expr>()?.into_token_stream(),
+        })
+    }
+}
+
+pub struct ExternalItem {
+    pub name: Ident,
+    pub ty: Type,
+}
+
+impl Parse for ExternalItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<kw::external>()?;
+        Ok(Self {
+            name: input.parse()?,
+            ty: input.parse()?,
+        })
+    }
+}
+
+pub struct StateItem {
+    pub docs: Vec<Attribute>,
+    pub name: Ident,
+    pub ty: Type,
+}
+
+impl Parse for StateItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<kw::state>()?;
+        Ok(Self {
+            docs: Vec::new(),
+            name: input.parse()?,
+            ty: input.parse()?,
+        })
+    }
+}
+
+pub struct CheckItem {
+    pub keyword: kw::check,
+    pub name: Option<Ident>,
+    pub params: Vec<Param>,
+    pub block: TokenStream,
+}
+
+impl Parse for CheckItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            keyword: input.parse()?,
+            name: input.parse()?,
+            params: parse_params(input)?,
+            block: parse_block(input)?,
+        })
+    }
+}
+
+pub struct ListenerItem {
+    pub name: Ident,
+    pub params: Vec<Param>,
+    pub block: TokenStream,
+}
+
+impl Parse for ListenerItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<kw::listener>()?;
+        Ok(Self {
+            name: input.parse()?,
+            params: parse_params(input)?,
+            block: parse_block(input)?,
+        })
+    }
+}
+
+pub struct SolveItem {
+    pub keyword: kw::solve,
+    pub unknowns: Vec<Unknown>,
+    // The residual of each equation, `(lhs) - (rhs)`.
+    pub residuals: Vec<TokenStream>,
+    pub equations: Vec<String>,
+}
+
+impl Parse for SolveItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let keyword = input.parse::<kw::solve>()?;
+        let content;
+        parenthesized!(content in input);
+        let unknowns: Vec<Unknown> = Punctuated::<Unknown, Token![,]>::parse_terminated(&content)?.into_iter().collect();
+        if unknowns.is_empty() {
+            return Err(syn::Error::new(keyword.span, "solve needs at least one unknown"));
+        }
+        let content;
+        braced!(content in input);
+        let mut residuals = Vec::new();
+        let mut equations = Vec::new();
+        for equation in Punctuated::<Expr, Token![;]>::parse_terminated(&content)? {
+            equations.push(equation.to_token_stream().to_string());
+            match equation {
+                Expr::Binary(syn::ExprBinary { left, op: syn::BinOp::Eq(_), right, .. }) => residuals.push(quote! { (#left) - (#right) }),
+                equation => return Err(syn::Error::new_spanned(equation, "expected an equation, `lhs == rhs`")),
+            }
+        }
+        if residuals.is_empty() {
+            return Err(syn::Error::new(keyword.span, "solve needs at least one equation"));
+        }
+        Ok(Self {
+            keyword,
+            unknowns,
+            residuals,
+            equations,
+        })
+    }
+}
+
+// `x` or `x = guess`, the value the solver starts from when the constrainer is created.
+pub struct Unknown {
+    pub name: Ident,
+    pub guess: Option<TokenStream>,
+}
+
+impl Parse for Unknown {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let guess = if input.parse::<Option<Token![=]>>()?.is_some() {
+            Some(input.parse::<Expr>()?.into_token_stream())
+        } else {
+            None
+        };
+        Ok(Self {
+            name,
+            guess,
+        })
+    }
+}
+
+pub struct SketchItem {
+    pub name: Ident,
+    pub primitives: Vec<Primitive>,
+    pub constraints: Vec<SketchConstraint>,
+}
+
+impl Parse for SketchItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<kw::sketch>()?;
+        let name = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut primitives = Vec::new();
+        let mut constraints = Vec::new();
+        while !content.is_empty() {
+            if content.peek(kw::point) || content.peek(kw::line) || content.peek(kw::circle) {
+                primitives.push(content.parse()?);
+            } else {
+                constraints.push(content.parse()?);
+            }
+        }
+        Ok(Self {
+            name,
+            primitives,
+            constraints,
+        })
+    }
+}
+
+pub enum Primitive {
+    // `point name (x, y)`, starting at `(x, y)`.
+    Point {
+        name: Ident,
+        x: TokenStream,
+        y: TokenStream,
+    },
+    // `line name (start, end)` between two points.
+    Line {
+        name: Ident,
+        start: Ident,
+        end: Ident,
+    },
+    // `circle name (center, radius)` around a point, starting with `radius`.
+    Circle {
+        name: Ident,
+        center: Ident,
+        radius: TokenStream,
+    },
+}
+
+impl Primitive {
+    pub fn name(&self) -> &Ident {
+        match self {
+            Primitive::Point { name, .. } | Primitive::Line { name, .. } | Primitive::Circle { name, .. } => name,
+        }
+    }
+}
+
+impl Parse for Primitive {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(kw::point) {
+            input.parse::<kw::point>()?;
+            let name = input.parse()?;
+            let content;
+            parenthesized!(content in input);
+            let x = content.parse::<Expr>()?.into_token_stream();
+            content.parse::<Token![,]>()?;
+            let y = content.parse::<Expr>()?.into_token_stream();
+            content.parse::<Option<Token![,]>>()?;
+            Ok(Primitive::Point {
+                name,
+                x,
+                y,
+            })
+        } else if lookahead.peek(kw::line) {
+            input.parse::<kw::line>()?;
+            let name = input.parse()?;
+            let content;
+            parenthesized!(content in input);
+            let start = content.parse()?;
+            content.parse::<Token![,]>()?;
+            let end = content.parse()?;
+            content.parse::<Option<Token![,]>>()?;
+            Ok(Primitive::Line {
+                name,
+                start,
+                end,
+            })
+        } else if lookahead.peek(kw::circle) {
+            input.parse::<kw::circle>()?;
+            let name = input.parse()?;
+            let content;
+            parenthesized!(content in input);
+            let center = content.parse()?;
+            content.parse::<Token![,]>()?;
+            let radius = content.parse::<Expr>()?.into_token_stream();
+            content.parse::<Option<Token![,]>>()?;
+            Ok(Primitive::Circle {
+                name,
+                center,
+                radius,
+            })
+        } else {
+            Err(lookahead.error())
+        }
+    }
+}
+
+// `kind (primitives)`, followed by `== value` for the kinds that take one.
+pub struct SketchConstraint {
+    pub keyword: Ident,
+    pub kind: ConstraintKind,
+    pub primitives: Vec<Ident>,
+    pub value: Option<Expr>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    Coincident,
+    Distance,
+    Angle,
+    Parallel,
+    Perpendicular,
+    Horizontal,
+    Vertical,
+    Tangent,
+    Radius,
+    Fix,
+}
+
+impl ConstraintKind {
+    const ALL: [(&'static str, ConstraintKind); 10] = [
+        ("coincident", ConstraintKind::Coincident),
+        ("distance", ConstraintKind::Distance),
+        ("angle", ConstraintKind::Angle),
+        ("parallel", ConstraintKind::Parallel),
+        ("perpendicular", ConstraintKind::Perpendicular),
+        ("horizontal", ConstraintKind::Horizontal),
+        ("vertical", ConstraintKind::Vertical),
+        ("tangent", ConstraintKind::Tangent),
+        ("radius", ConstraintKind::Radius),
+        ("fix", ConstraintKind::Fix),
+    ];
+
+    fn takes_value(self) -> bool {
+        matches!(self, ConstraintKind::Distance | ConstraintKind::Angle | ConstraintKind::Radius | ConstraintKind::Fix)
+    }
+}
+
+impl Parse for SketchConstraint {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let keyword: Ident = input.parse()?;
+        let kind = match ConstraintKind::ALL.iter().find(|(name, _)| keyword == name) {
+            Some((_, kind)) => *kind,
+            None => {
+                let kinds: Vec<String> = ConstraintKind::ALL.iter().map(|(name, _)| format!("`{}`", name)).collect();
+                return Err(syn::Error::new(keyword.span(), format!("expected `point`, `line`, `circle` or a constraint: {}", kinds.join(", "))));
+            },
+        };
+        let primitives = parse_idents(input)?;
+        let value = if kind.takes_value() {
+            input.parse::<Token![==]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self {
+            keyword,
+            kind,
+            primitives,
+            value,
+        })
+    }
+}
+
+pub struct OpGenSetItem {
+    pub dynamics: Vec<Ident>,
+}
+
+impl Parse for OpGenSetItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let keyword = input.parse::<kw::opgenset>()?;
+        let dynamics = parse_idents(input)?;
+        if dynamics.is_empty() {
+            return Err(syn::Error::new(keyword.span, "opgenset needs at least one dynamic"));
+        }
+        Ok(Self {
+            dynamics,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Param {
+    pub name: Ident,
+    pub passing: Passing,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Passing {
+    // `&T`, the default.
+    Ref,
+    // `move x`: `T`, copied out of the constrainer.
+    Move,
+    // `clone x`: `T`, cloned out of the constrainer.
+    Clone,
+    // `x changed`: `Change<T>`, the previous and current value. Only for listeners.
+    Changed,
+    // `mut x`: `&mut T`. Only for state taken by listeners.
+    Mut,
+}
+
+impl Parse for Param {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let passing = if input.peek(Token![move]) {
+            input.parse::<Token![move]>()?;
+            Passing::Move
+        } else if input.peek(kw::clone) && input.peek2(syn::Ident) {
+            input.parse::<kw::clone>()?;
+            Passing::Clone
+        } else if input.peek(Token![mut]) {
+            input.parse::<Token![mut]>()?;
+            Passing::Mut
+        } else {
+            Passing::Ref
+        };
+        let name = input.parse()?;
+        if input.peek(kw::changed) {
+            let keyword = input.parse::<kw::changed>()?;
+            if passing != Passing::Ref {
+                return Err(syn::Error::new(keyword.span, "Changes are always passed by reference"));
+            }
+            return Ok(Self {
+                name,
+                passing: Passing::Changed,
+            });
+        }
+        Ok(Self {
+            name,
+            passing,
+        })
+    }
+}
+
+fn parse_params(input: ParseStream) -> syn::Result<Vec<Param>> {
+    let content;
+    parenthesized!(content in input);
+    let params = Punctuated::<Param, Token![,]>::parse_terminated(&content)?;
+    Ok(params.into_iter().collect())
+}
+
+fn parse_idents(input: ParseStream) -> syn::Result<Vec<Ident>> {
+    let content;
+    parenthesized!(content in input);
+    let idents = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+    Ok(idents.into_iter().collect())
+}
+
+fn parse_block(input: ParseStream) -> syn::Result<TokenStream> {
+    let content;
+    braced!(content in input);
+    content.parse()
+}
+
+// A type such as `f32` directly followed by `(x, y)` would otherwise be parsed as the
+// parenthesized sugar of `Fn(x, y)`, so the type ends at the first top-level parenthesized
+// group that is followed by the constrained's block.
+fn parse_type_before_params(input: ParseStream) -> syn::Result<Type> {
+    let span = input.span();
+    let tokens = input.step(|cursor| {
+        let mut tokens = TokenStream::new();
+        let mut rest = *cursor;
+        let mut angle_depth = 0usize;
+        let mut after_joint_minus = false;
+        while let Some((tt, next)) = rest.token_tree() {
+            if angle_depth == 0 && rest.group(Delimiter::Parenthesis).is_some() && starts_block(next) {
+                break;
+            }
+            let mut joint_minus = false;
+            if let TokenTree::Punct(punct) = &tt {
+                match punct.as_char() {
+                    '<' => angle_depth += 1,
+                    '>' if !after_joint_minus => angle_depth = angle_depth.saturating_sub(1),
+                    '-' => joint_minus = punct.spacing() == Spacing::Joint,
+                    _ => {},
+                }
+            }
+            after_joint_minus = joint_minus;
+            tokens.extend(Some(tt));
+            rest = next;
+        }
+        Ok((tokens, rest))
+    })?;
+    if tokens.is_empty() {
+        return Err(syn::Error::new(span, "expected a type"));
+    }
+    syn::parse2(tokens)
+}
+
+// Whether `cursor` is at the block of a constrained, possibly preceded by `try`.
+fn starts_block(cursor: Cursor) -> bool {
+    let cursor = match cursor.ident() {
+        Some((ident, next)) if ident == "try" => next,
+        _ => cursor,
+    };
+    cursor.group(Delimiter::Brace).is_some()
+}
@@ -0,0 +1,448 @@
+use proc_macro2::{
+    Ident,
+    Span,
+    TokenStream,
+};
+use quote::{
+    TokenStreamExt,
+    quote,
+};
+
+use std::collections::{
+    BTreeMap,
+    HashMap,
+    HashSet,
+};
+
+use syn::Type;
+
+use crate::graph::{
+    Check,
+    Constrained,
+    External,
+    Graph,
+    Identifier,
+    Solve,
+};
+use crate::parse::{
+    Modes,
+    Param,
+    Passing,
+};
+
+// Whether an identifier has to be treated as changed: either known while expanding the macro
+// or only once the generated code runs, in which case the ident is a local `bool`.
+#[derive(Clone)]
+pub enum Dirty {
+    Always,
+    When(Ident),
+}
+
+pub struct Propagation {
+    pub block: TokenStream,
+    // `name: Ty,` arguments for the externals read by the propagation, keyed by index.
+    pub external_args: BTreeMap<usize, TokenStream>,
+    // Every dynamic and constrained that was set or recomputed, keyed by index.
+    pub changed: BTreeMap<usize, Dirty>,
+}
+
+// Recomputes every constrained depending on `sources` in topological order, then calls the
+// listeners of anything that changed, passing them the current value of every param.
+// `target` is the constrainer being updated and `owner` the type its compute and listener
+// functions are associated to. With `cutoff`, a recomputed
+// constrained only counts as changed if its new value differs from the old one. With
+// `subscriptions`, the subscribers of everything that changed are notified along with the
+// listeners.
+// Tracked sources must already have their previous value in an `Option` named by
+// `old_value`, `None` if they were not changed.
+pub fn propagate(
+    graph: &Graph,
+    sources: &BTreeMap<usize, Dirty>,
+    modes: Modes,
+    target: &TokenStream,
+    owner: &TokenStream,
+) -> Propagation {
+    let identifiers = &graph.identifiers;
+    let mut dirty: HashMap<usize, Dirty> = sources.iter().map(|(index, dirty)| (*index, dirty.clone())).collect();
+    let mut block = TokenStream::new();
+    let mut calls = TokenStream::new();
+    let mut external_args = BTreeMap::new();
+    let mut olds: HashSet<usize> = sources.keys().copied().filter(|index| graph.keeps_old(*index)).collect();
+    // Statements restoring everything changed so far, run if a constrained fails.
+    let mut rollback: Vec<TokenStream> = sources.keys()
+        .filter(|index| olds.contains(index))
+        .map(|index| restore(graph, *index, target))
+        .collect();
+
+    if modes.subscriptions {
+        for (index, source_dirty) in sources {
+            calls.append_all(notify(graph, *index, source_dirty, target, owner));
+        }
+    }
+
+    for index in graph.affected(&sources.keys().copied().collect()) {
+        let (name, identifier) = identifiers.get_index(index).unwrap();
+        // Checks are verified before anything is stored.
+        if let Identifier::Check(_) = identifier {
+            continue;
+        }
+        let condition = match condition(graph, identifier.params(), &dirty) {
+            Some(condition) => condition,
+            None => continue,
+        };
+
+        let mut args = TokenStream::new();
+        for param in identifier.params() {
+            let (param_index, _, param_identifier) = identifiers.get_full(&param.name).unwrap();
+            match param_identifier {
+                Identifier::Dynamic(_) | Identifier::Constrained(_) if param.passing == Passing::Changed && olds.contains(&param_index) => {
+                    let place = place(graph, target, owner, &param.name);
+                    let old = old_value(&param.name);
+                    args.append_all(quote! {
+                        ::constrainer::Change {
+                            old: match &#old {
+                                ::core::option::Option::Some(old) => old,
+                                ::core::option::Option::None => &#place,
+                            },
+                            new: &#place,
+                        },
+                    });
+                },
+                Identifier::Dynamic(_) | Identifier::Constrained(_) | Identifier::State(_) => {
+                    let arg = pass(place(graph, target, owner, &param.name), param.passing);
+                    args.append_all(quote! {
+                        #arg,
+                    });
+                },
+                Identifier::External(External {
+                    ty,
+                    ..
+                }) => {
+                    let param_name = &param.name;
+                    external_args.insert(param_index, quote! {
+                        #param_name: #ty,
+                    });
+                    let arg = pass(quote! { #param_name }, param.passing);
+                    args.append_all(quote! {
+                        #arg,
+                    });
+                },
+                Identifier::Check(_) | Identifier::Listener(_) => unreachable!()
+            }
+        }
+
+        match identifier {
+            Identifier::Constrained(constrained) => {
+                let flag = dirty_flag(name);
+                let old = old_value(name);
+                // Solved again starting from the previous solution.
+                if constrained.solve.is_some() {
+                    args = quote! { &#target.#name, #args };
+                }
+                let compute = compute(owner, name, constrained, &args, &rollback);
+                if constrained.lazy && graph.keeps_old(index) {
+                    // The whole cell is taken out to be put back on failure, since the getters
+                    // called by later constraineds may fill it again. `None` if it wasn't emptied.
+                    match condition {
+                        None => {
+                            block.append_all(quote! {
+                                let #old = ::core::option::Option::Some(::core::mem::take(&mut #target.#name));
+                            });
+                            dirty.insert(index, Dirty::Always);
+                        },
+                        Some(condition) => {
+                            block.append_all(quote! {
+                                let #flag = #condition;
+                                let #old = if #flag {
+                                    ::core::option::Option::Some(::core::mem::take(&mut #target.#name))
+                                } else {
+                                    ::core::option::Option::None
+                                };
+                            });
+                            dirty.insert(index, Dirty::When(flag));
+                        },
+                    }
+                    olds.insert(index);
+                    rollback.push(restore(graph, index, target));
+                } else if graph.keeps_old(index) {
+                    let replace = quote! {
+                        ::core::option::Option::Some(::core::mem::replace(&mut #target.#name, value))
+                    };
+                    let update = if modes.cutoff {
+                        quote! {
+                            let value = #compute;
+                            if value != #target.#name {
+                                #replace
+                            } else {
+                                ::core::option::Option::None
+                            }
+                        }
+                    } else {
+                        quote! {
+                            let value = #compute;
+                            #replace
+                        }
+                    };
+                    block.append_all(match &condition {
+                        None => quote! {
+                            let #old = { #update };
+                        },
+                        Some(condition) => quote! {
+                            let #old = if #condition {
+                                #update
+                            } else {
+                                ::core::option::Option::None
+                            };
+                        },
+                    });
+                    olds.insert(index);
+                    rollback.push(restore(graph, index, target));
+                    if modes.cutoff || condition.is_some() {
+                        block.append_all(quote! {
+                            let #flag = #old.is_some();
+                        });
+                        dirty.insert(index, Dirty::When(flag));
+                    } else {
+                        dirty.insert(index, Dirty::Always);
+                    }
+                } else if modes.cutoff && !constrained.lazy {
+                    let update = quote! {
+                        let value = #compute;
+                        if value != #target.#name {
+                            #target.#name = value;
+                            true
+                        } else {
+                            false
+                        }
+                    };
+                    let changed = match condition {
+                        None => quote! { { #update } },
+                        Some(condition) => quote! {
+                            if #condition {
+                                #update
+                            } else {
+                                false
+                            }
+                        },
+                    };
+                    block.append_all(quote! {
+                        let #flag = #changed;
+                    });
+                    dirty.insert(index, Dirty::When(flag));
+                } else {
+                    let update = if constrained.lazy {
+                        quote! {
+                            #target.#name.take();
+                        }
+                    } else {
+                        quote! {
+                            #target.#name = #compute;
+                        }
+                    };
+                    match condition {
+                        None => {
+                            block.append_all(update);
+                            dirty.insert(index, Dirty::Always);
+                        },
+                        Some(condition) => {
+                            block.append_all(quote! {
+                                let #flag = #condition;
+                                if #flag {
+                                    #update
+                                }
+                            });
+                            dirty.insert(index, Dirty::When(flag));
+                        },
+                    }
+                }
+                if modes.subscriptions && !identifier.is_hidden() {
+                    calls.append_all(notify(graph, index, &dirty[&index], target, owner));
+                }
+            },
+            Identifier::Listener(_) => {
+                let call = quote! {
+                    #owner::#name(#args);
+                };
+                calls.append_all(match condition {
+                    None => call,
+                    Some(condition) => quote! {
+                        if #condition {
+                            #call
+                        }
+                    },
+                });
+            },
+            Identifier::Dynamic(_) | Identifier::External(_) | Identifier::State(_) | Identifier::Check(_) => unreachable!(),
+        }
+    }
+
+    block.append_all(calls);
+    Propagation {
+        block,
+        external_args,
+        changed: dirty.into_iter().collect(),
+    }
+}
+
+// A place expression for the current value of a dynamic, constrained or state of `target`,
+// computing it if it is lazy. Only the fields needed are borrowed, so that listeners can take
+// state by `mut` next to it.
+pub fn place(graph: &Graph, target: &TokenStream, owner: &TokenStream, param: &Ident) -> TokenStream {
+    match &graph.identifiers[param] {
+        Identifier::Constrained(constrained) if constrained.lazy => {
+            let compute_fn_name = &constrained.compute_fn_name;
+            let args = constrained.params.iter().map(|param| pass(place(graph, target, owner, &param.name), param.passing));
+            quote! { (*#target.#param.get_or_init(|| #owner::#compute_fn_name(#(#args,)*))) }
+        },
+        _ => quote! { #target.#param },
+    }
+}
+
+// Passes the value at `place` the way a param asks for it.
+pub fn pass(place: TokenStream, passing: Passing) -> TokenStream {
+    match passing {
+        Passing::Ref => quote! { &#place },
+        Passing::Move => place,
+        Passing::Clone => quote! { #place.clone() },
+        // Without a previous value around, nothing changed.
+        Passing::Changed => quote! { ::constrainer::Change { old: &#place, new: &#place } },
+        Passing::Mut => quote! { &mut #place },
+    }
+}
+
+// The type of a compute or listener function argument for a param of type `ty`.
+pub fn param_ty(ty: &Type, passing: Passing) -> TokenStream {
+    match passing {
+        Passing::Ref => quote! { &#ty },
+        Passing::Move | Passing::Clone => quote! { #ty },
+        Passing::Changed => quote! { ::constrainer::Change<'_, #ty> },
+        Passing::Mut => quote! { &mut #ty },
+    }
+}
+
+pub fn dirty_flag(name: &Ident) -> Ident {
+    Ident::new(&format!("__dirty_{}", name), Span::call_site())
+}
+
+pub fn old_value(name: &Ident) -> Ident {
+    Ident::new(&format!("__old_{}", name), Span::call_site())
+}
+
+// A call to the compute function of a constrained. If it can fail, everything in `rollback`
+// is restored and the error returned.
+pub fn compute(owner: &TokenStream, name: &Ident, constrained: &Constrained, args: &TokenStream, rollback: &[TokenStream]) -> TokenStream {
+    let compute_fn_name = &constrained.compute_fn_name;
+    if let Some(Solve { description, equation_names, .. }) = constrained.solve.as_deref() {
+        return quote! {
+            match #owner::#compute_fn_name(#args) {
+                ::core::result::Result::Ok(value) => value,
+                ::core::result::Result::Err(source) => {
+                    #(#rollback)*
+                    return ::core::result::Result::Err(::constrainer::ConstrainerError::Solve {
+                        unknowns: #description,
+                        conflicting: ::constrainer::equation_names(&source.unsatisfied, &[#(#equation_names),*]),
+                        source,
+                    });
+                },
+            }
+        };
+    }
+    if !constrained.fallible {
+        return quote! { #owner::#compute_fn_name(#args) };
+    }
+    let name_str = name.to_string();
+    quote! {
+        match ::core::result::Result::map_err(#owner::#compute_fn_name(#args), ::core::convert::Into::<::constrainer::BoxError>::into) {
+            ::core::result::Result::Ok(value) => value,
+            ::core::result::Result::Err(source) => {
+                #(#rollback)*
+                return ::core::result::Result::Err(::constrainer::ConstrainerError::Compute {
+                    constrained: #name_str,
+                    source,
+                });
+            },
+        }
+    }
+}
+
+// Returns an error naming the check if it doesn't hold.
+pub fn verify(owner: &TokenStream, check: &Check, args: &TokenStream) -> TokenStream {
+    let check_fn_name = &check.check_fn_name;
+    let description = &check.description;
+    quote! {
+        if !#owner::#check_fn_name(#args) {
+            return ::core::result::Result::Err(::constrainer::ConstrainerError::Check {
+                check: #description,
+            });
+        }
+    }
+}
+
+// The return type of `new`, setters and transactions, wrapped in a `Result` if the
+// constrainer can fail.
+pub fn output(graph: &Graph, ty: TokenStream) -> TokenStream {
+    if graph.fallible {
+        quote! { ::core::result::Result<#ty, ::constrainer::ConstrainerError> }
+    } else {
+        ty
+    }
+}
+
+// Returns `value` as `output` would have it.
+pub fn output_value(graph: &Graph, value: TokenStream) -> TokenStream {
+    if graph.fallible {
+        quote! { ::core::result::Result::Ok(#value) }
+    } else {
+        value
+    }
+}
+
+// Puts back the previous value of a dynamic or constrained kept by propagation.
+fn restore(graph: &Graph, index: usize, target: &TokenStream) -> TokenStream {
+    let name = graph.identifiers.get_index(index).unwrap().0;
+    let old = old_value(name);
+    quote! {
+        if let ::core::option::Option::Some(old) = #old {
+            #target.#name = old;
+        }
+    }
+}
+
+pub fn subscribers_field(name: &Ident) -> Ident {
+    Ident::new(&format!("__subscribers_{}", name), Span::call_site())
+}
+
+// Calls the subscribers of a dynamic or constrained if it changed. Lazy constraineds are only
+// computed for it if someone subscribed.
+fn notify(graph: &Graph, index: usize, dirty: &Dirty, target: &TokenStream, owner: &TokenStream) -> TokenStream {
+    let name = graph.identifiers.get_index(index).unwrap().0;
+    let subscribers = subscribers_field(name);
+    let place = place(graph, target, owner, name);
+    let condition = match dirty {
+        Dirty::Always => quote! { !#target.#subscribers.is_empty() },
+        Dirty::When(flag) => quote! { #flag && !#target.#subscribers.is_empty() },
+    };
+    quote! {
+        if #condition {
+            #target.#subscribers.notify(&#place);
+        }
+    }
+}
+
+// `None` if nothing `params` refers to can have changed, `Some(None)` if one of them always
+// changed and `Some(Some(condition))` if that is only known at runtime.
+fn condition(graph: &Graph, params: &[Param], dirty: &HashMap<usize, Dirty>) -> Option<Option<TokenStream>> {
+    let mut flags = Vec::new();
+    for param in params {
+        match dirty.get(&graph.identifiers.get_index_of(&param.name).unwrap()) {
+            Some(Dirty::Always) => return Some(None),
+            Some(Dirty::When(flag)) => flags.push(flag),
+            None => {},
+        }
+    }
+    if flags.is_empty() {
+        None
+    } else {
+        Some(Some(quote! { #(#flags)||* }))
+    }
+}
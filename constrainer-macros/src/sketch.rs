@@ -0,0 +1,284 @@
+use proc_macro2::{
+    Ident,
+    Span,
+    TokenStream,
+};
+use quote::{
+    TokenStreamExt,
+    quote,
+};
+use syn::{
+    parse_quote,
+    Expr,
+    Type,
+};
+
+use std::collections::{
+    BTreeSet,
+    HashMap,
+};
+
+use crate::graph::{
+    Constrained,
+    Errors,
+    Identifier,
+    Solve,
+};
+use crate::parse::{
+    ConstraintKind,
+    Param,
+    Passing,
+    Primitive,
+    SketchConstraint,
+    SketchItem,
+};
+use crate::referenced_names;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Point,
+    Line,
+    Circle,
+}
+
+// A sketch is solved like a `solve` item whose unknowns are the coordinates of its points and
+// the radii of its circles. Each point, line and circle is then a constrained built from the
+// solution, so that its getter returns it whole.
+pub fn sketch(item: SketchItem, value_names: &BTreeSet<String>) -> syn::Result<Vec<(Ident, Identifier)>> {
+    let mut errors = Errors::default();
+    let solution_name = Ident::new(&format!("__sketch_{}", item.name), item.name.span());
+    let kinds: HashMap<String, Kind> = item.primitives.iter()
+        .map(|primitive| {
+            let kind = match primitive {
+                Primitive::Point { .. } => Kind::Point,
+                Primitive::Line { .. } => Kind::Line,
+                Primitive::Circle { .. } => Kind::Circle,
+            };
+            (primitive.name().to_string(), kind)
+        })
+        .collect();
+
+    let mut unknowns = Vec::new();
+    let mut unknown_names = Vec::new();
+    let mut guesses = Vec::new();
+    // Points are bound first, for lines and circles to be built from them.
+    let mut point_bindings = TokenStream::new();
+    let mut bindings = TokenStream::new();
+    let mut primitives = Vec::new();
+    for primitive in &item.primitives {
+        match primitive {
+            Primitive::Point { name, x, y } => {
+                let position = unknowns.len();
+                let next_position = position+1;
+                let unknown_x = Ident::new(&format!("__{}_x", name), Span::call_site());
+                let unknown_y = Ident::new(&format!("__{}_y", name), Span::call_site());
+                point_bindings.append_all(quote! {
+                    let #name = ::constrainer::sketch::Point::new(#unknown_x, #unknown_y);
+                });
+                unknowns.extend([unknown_x, unknown_y]);
+                unknown_names.extend([format!("{}.x", name), format!("{}.y", name)]);
+                guesses.extend([x.clone(), y.clone()]);
+                primitives.push((name.clone(), constrained(
+                    parse_quote! { ::constrainer::sketch::Point },
+                    vec![&solution_name],
+                    quote! { ::constrainer::sketch::Point::new(#solution_name[#position], #solution_name[#next_position]) },
+                )));
+            },
+            Primitive::Line { name, start, end } => {
+                expect_point(&kinds, start, &mut errors);
+                expect_point(&kinds, end, &mut errors);
+                if start == end {
+                    errors.push(syn::Error::new(end.span(), "A line needs two different points."));
+                }
+                bindings.append_all(quote! {
+                    let #name = ::constrainer::sketch::Line::new(#start, #end);
+                });
+                primitives.push((name.clone(), constrained(
+                    parse_quote! { ::constrainer::sketch::Line },
+                    vec![start, end],
+                    quote! { ::constrainer::sketch::Line::new(*#start, *#end) },
+                )));
+            },
+            Primitive::Circle { name, center, radius } => {
+                expect_point(&kinds, center, &mut errors);
+                let position = unknowns.len();
+                let unknown_radius = Ident::new(&format!("__{}_radius", name), Span::call_site());
+                bindings.append_all(quote! {
+                    let #name = ::constrainer::sketch::Circle::new(#center, #unknown_radius);
+                });
+                unknowns.push(unknown_radius);
+                unknown_names.push(format!("{}.radius", name));
+                guesses.push(radius.clone());
+                primitives.push((name.clone(), constrained(
+                    parse_quote! { ::constrainer::sketch::Circle },
+                    vec![center, &solution_name],
+                    quote! { ::constrainer::sketch::Circle::new(*#center, #solution_name[#position]) },
+                )));
+            },
+        }
+    }
+
+    let mut residuals = Vec::new();
+    let mut equation_names = Vec::new();
+    for constraint in &item.constraints {
+        match constraint_residuals(constraint, &kinds) {
+            Ok(constraint_residuals) => {
+                let primitives: Vec<String> = constraint.primitives.iter().map(|name| name.to_string()).collect();
+                let equation_name = format!("{} ({})", constraint.keyword, primitives.join(", "));
+                equation_names.extend(constraint_residuals.iter().map(|_| equation_name.clone()));
+                residuals.extend(constraint_residuals);
+            },
+            Err(error) => errors.push(error),
+        }
+    }
+    errors.finish()?;
+
+    // Primitives of the sketch are locals of the residuals, everything else they name is a param.
+    let mut params: Vec<Param> = Vec::new();
+    for residual in &residuals {
+        for name in referenced_names(residual.clone(), value_names) {
+            if !kinds.contains_key(&name.to_string()) && !params.iter().any(|param| param.name == name) {
+                params.push(Param {
+                    name,
+                    passing: Passing::Ref,
+                });
+            }
+        }
+    }
+
+    let count = unknowns.len();
+    let mut declarations = vec![(solution_name.clone(), Identifier::Constrained(Constrained {
+        ty: parse_quote! { [f64; #count] },
+        lazy: false,
+        params,
+        fallible: true,
+        block: TokenStream::new(),
+        compute_fn_name: Ident::new(&format!("solve_{}", item.name), Span::call_site()),
+        inverse: None,
+        solve: Some(Box::new(Solve {
+            unknowns,
+            unknown_names,
+            equation_names,
+            guesses,
+            bindings: quote! { #point_bindings #bindings },
+            residuals,
+            residuals_fn_name: Ident::new(&format!("residuals_{}", item.name), Span::call_site()),
+            description: item.name.to_string(),
+        })),
+        dependents: BTreeSet::new(),
+    }))];
+    for (name, (ty, params, block)) in primitives {
+        let compute_fn_name = Ident::new(&format!("compute_{}", name), Span::call_site());
+        declarations.push((name, Identifier::Constrained(Constrained {
+            ty,
+            lazy: false,
+            params,
+            fallible: false,
+            block,
+            compute_fn_name,
+            inverse: None,
+            solve: None,
+            dependents: BTreeSet::new(),
+        })));
+    }
+    Ok(declarations)
+}
+
+fn constrained(ty: Type, params: Vec<&Ident>, block: TokenStream) -> (Type, Vec<Param>, TokenStream) {
+    let params = params.into_iter()
+        .map(|name| Param {
+            name: name.clone(),
+            passing: Passing::Ref,
+        })
+        .collect();
+    (ty, params, block)
+}
+
+fn expect_point(kinds: &HashMap<String, Kind>, name: &Ident, errors: &mut Errors) {
+    if kinds.get(&name.to_string()) != Some(&Kind::Point) {
+        errors.push(syn::Error::new(name.span(), format!("`{}` is not a point of this sketch", name)));
+    }
+}
+
+// The residuals of a constraint, expressions that are zero when it holds.
+fn constraint_residuals(constraint: &SketchConstraint, kinds: &HashMap<String, Kind>) -> syn::Result<Vec<TokenStream>> {
+    let mut args = Vec::new();
+    for name in &constraint.primitives {
+        match kinds.get(&name.to_string()) {
+            Some(kind) => args.push((*kind, name)),
+            None => return Err(syn::Error::new(name.span(), format!("`{}` is not a point, line or circle of this sketch", name))),
+        }
+    }
+    // Pairs of different kinds are taken in the order point, line, circle.
+    if let [(first, _), (second, _)] = args.as_slice() {
+        if *first as u8 > *second as u8 {
+            args.swap(0, 1);
+        }
+    }
+    let value = constraint.value.as_ref().map(|value| quote! { (#value) });
+    let residuals = match (constraint.kind, args.as_slice()) {
+        (ConstraintKind::Coincident, [(Kind::Point, a), (Kind::Point, b)]) => vec![
+            quote! { #a.x - #b.x },
+            quote! { #a.y - #b.y },
+        ],
+        (ConstraintKind::Coincident, [(Kind::Point, point), (Kind::Line, line)]) => vec![
+            quote! { #line.signed_distance(#point) },
+        ],
+        (ConstraintKind::Coincident, [(Kind::Point, point), (Kind::Circle, circle)]) => vec![
+            quote! { #point.distance(#circle.center) - #circle.radius },
+        ],
+        (ConstraintKind::Distance, [(Kind::Point, a), (Kind::Point, b)]) => vec![
+            quote! { #a.distance(#b) - #value },
+        ],
+        (ConstraintKind::Distance, [(Kind::Point, point), (Kind::Line, line)]) => vec![
+            quote! { #line.distance(#point) - #value },
+        ],
+        (ConstraintKind::Angle, [(Kind::Line, a), (Kind::Line, b)]) => vec![
+            quote! { ::constrainer::sketch::angle_difference(#a.angle_to(#b) - #value, 0.0) },
+        ],
+        (ConstraintKind::Parallel, [(Kind::Line, a), (Kind::Line, b)]) => vec![
+            quote! { #a.angle_to(#b).sin() },
+        ],
+        (ConstraintKind::Perpendicular, [(Kind::Line, a), (Kind::Line, b)]) => vec![
+            quote! { #a.angle_to(#b).cos() },
+        ],
+        (ConstraintKind::Horizontal, [(Kind::Line, line)]) => vec![
+            quote! { #line.start.y - #line.end.y },
+        ],
+        (ConstraintKind::Vertical, [(Kind::Line, line)]) => vec![
+            quote! { #line.start.x - #line.end.x },
+        ],
+        (ConstraintKind::Tangent, [(Kind::Line, line), (Kind::Circle, circle)]) => vec![
+            quote! { #line.distance(#circle.center) - #circle.radius },
+        ],
+        (ConstraintKind::Tangent, [(Kind::Circle, a), (Kind::Circle, b)]) => vec![
+            quote! { #a.center.distance(#b.center) - (#a.radius + #b.radius) },
+        ],
+        (ConstraintKind::Radius, [(Kind::Circle, circle)]) => vec![
+            quote! { #circle.radius - #value },
+        ],
+        (ConstraintKind::Fix, [(Kind::Point, point)]) => match &constraint.value {
+            Some(Expr::Tuple(tuple)) if tuple.elems.len() == 2 => {
+                let (x, y) = (&tuple.elems[0], &tuple.elems[1]);
+                vec![
+                    quote! { #point.x - (#x) },
+                    quote! { #point.y - (#y) },
+                ]
+            },
+            value => return Err(syn::Error::new_spanned(value, "expected a position, `(x, y)`")),
+        },
+        (kind, _) => {
+            let expected = match kind {
+                ConstraintKind::Coincident => "two points, or a point and a line or circle",
+                ConstraintKind::Distance => "two points, or a point and a line",
+                ConstraintKind::Angle | ConstraintKind::Parallel | ConstraintKind::Perpendicular => "two lines",
+                ConstraintKind::Horizontal | ConstraintKind::Vertical => "a line",
+                ConstraintKind::Tangent => "a line and a circle, or two circles",
+                ConstraintKind::Radius => "a circle",
+                ConstraintKind::Fix => "a point",
+            };
+            return Err(syn::Error::new(constraint.keyword.span(), format!("`{}` takes {}", constraint.keyword, expected)));
+        },
+    };
+    Ok(residuals)
+}
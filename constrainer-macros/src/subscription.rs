@@ -0,0 +1,80 @@
+use proc_macro2::{
+    Ident,
+    Span,
+    TokenStream,
+};
+use quote::{
+    TokenStreamExt,
+    quote,
+};
+
+use crate::graph::{
+    Graph,
+    Identifier,
+};
+use crate::propagate::subscribers_field;
+
+pub struct Subscriptions {
+    // Fields of the constrainer holding the subscribers.
+    pub fields: TokenStream,
+    // Their initial values, for `new`.
+    pub deliminated_fields: TokenStream,
+    // `subscribe_<name>` for every dynamic and constrained, and `unsubscribe`.
+    pub ops: TokenStream,
+}
+
+// Closures subscribed at runtime are called by setters and transactions with the new value of
+// what they subscribed to, right where a listener taking it would be called.
+pub fn subscriptions(graph: &Graph) -> Subscriptions {
+    let mut fields = TokenStream::new();
+    let mut deliminated_fields = TokenStream::new();
+    let mut ops = TokenStream::new();
+    let mut unsubscribes = Vec::new();
+
+    for (name, identifier) in &graph.identifiers {
+        let ty = match identifier {
+            Identifier::Constrained(_) if identifier.is_hidden() => continue,
+            Identifier::Dynamic(_) | Identifier::Constrained(_) => identifier.ty().unwrap(),
+            Identifier::External(_) | Identifier::State(_) | Identifier::Check(_) | Identifier::Listener(_) => continue,
+        };
+        let subscribers = subscribers_field(name);
+        fields.append_all(quote! {
+            #subscribers: ::constrainer::Subscribers<#ty>,
+        });
+        deliminated_fields.append_all(quote! {
+            #subscribers: ::constrainer::Subscribers::new(),
+        });
+        let subscribe_fn_name = Ident::new(&format!("subscribe_{}", name), Span::call_site());
+        ops.append_all(quote! {
+            pub fn #subscribe_fn_name(&mut self, subscriber: ::std::boxed::Box<dyn FnMut(&#ty)>) -> ::constrainer::SubscriptionId {
+                let id = self.__next_subscription.take_next();
+                self.#subscribers.subscribe(id, subscriber);
+                id
+            }
+        });
+        unsubscribes.push(quote! {
+            self.#subscribers.unsubscribe(id)
+        });
+    }
+
+    fields.append_all(quote! {
+        __next_subscription: ::constrainer::SubscriptionId,
+    });
+    deliminated_fields.append_all(quote! {
+        __next_subscription: ::constrainer::SubscriptionId::first(),
+    });
+    if unsubscribes.is_empty() {
+        unsubscribes.push(quote! { false });
+    }
+    ops.append_all(quote! {
+        pub fn unsubscribe(&mut self, id: ::constrainer::SubscriptionId) -> bool {
+            #(#unsubscribes)||*
+        }
+    });
+
+    Subscriptions {
+        fields,
+        deliminated_fields,
+        ops,
+    }
+}
@@ -0,0 +1,178 @@
+use proc_macro2::{
+    Ident,
+    Span,
+    TokenStream,
+};
+use quote::{
+    TokenStreamExt,
+    quote,
+};
+use syn::{
+    parse_quote,
+    Generics,
+    Visibility,
+};
+
+use std::collections::BTreeMap;
+
+use crate::changed::{
+    changed_name,
+    changed_value,
+};
+use crate::graph::{
+    Graph,
+    Identifier,
+    Normalize,
+};
+use crate::parse::Modes;
+use crate::propagate::{
+    dirty_flag,
+    old_value,
+    output,
+    output_value,
+    pass,
+    verify,
+    propagate,
+    Dirty,
+};
+
+pub struct Transaction {
+    // The transaction struct and its impl.
+    pub items: TokenStream,
+    // `begin` and `update`, for the impl of the constrainer.
+    pub ops: TokenStream,
+}
+
+// Dynamics set through a transaction are only stored when it is committed, after which every
+// constrained depending on at least one of them is recomputed once.
+pub fn transaction(graph: &Graph, vis: &Visibility, name: &Ident, generics: &Generics, modes: Modes) -> Transaction {
+    let identifiers = &graph.identifiers;
+    let transaction_name = Ident::new(&format!("{}Transaction", name), Span::call_site());
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut transaction_generics = generics.clone();
+    transaction_generics.params.insert(0, parse_quote!('__constrainer));
+    let (transaction_impl_generics, transaction_ty_generics, _) = transaction_generics.split_for_impl();
+
+    let owner = quote! { <#name #ty_generics> };
+
+    let mut pending_fields = TokenStream::new();
+    let mut deliminated_pending = TokenStream::new();
+    let mut set_fns = TokenStream::new();
+    let mut store_dynamics = TokenStream::new();
+    let mut sources = BTreeMap::new();
+    for (index, (dynamic_name, identifier)) in identifiers.iter().enumerate() {
+        if let Identifier::Dynamic(dynamic) = identifier {
+            let ty = &dynamic.ty;
+            pending_fields.append_all(quote! {
+                #dynamic_name: ::core::option::Option<#ty>,
+            });
+            deliminated_pending.append_all(quote! {
+                #dynamic_name: ::core::option::Option::None,
+            });
+            let set_fn_name = Ident::new(&format!("set_{}", dynamic_name), Span::call_site());
+            let value = match &dynamic.normalize {
+                Some(Normalize { normalize_fn_name, .. }) => quote! { #owner::#normalize_fn_name(#dynamic_name) },
+                None => quote! { #dynamic_name },
+            };
+            set_fns.append_all(quote! {
+                pub fn #set_fn_name(&mut self, #dynamic_name: #ty) -> &mut Self {
+                    self.#dynamic_name = ::core::option::Option::Some(#value);
+                    self
+                }
+            });
+            if graph.keeps_old(index) {
+                let flag = dirty_flag(dynamic_name);
+                let old = old_value(dynamic_name);
+                store_dynamics.append_all(quote! {
+                    let #old = self.#dynamic_name.map(|__value| ::core::mem::replace(&mut __constrainer.#dynamic_name, __value));
+                    let #flag = #old.is_some();
+                });
+                sources.insert(index, Dirty::When(flag));
+            } else {
+                let flag = dirty_flag(dynamic_name);
+                store_dynamics.append_all(quote! {
+                    let #flag = if let ::core::option::Option::Some(__value) = self.#dynamic_name {
+                        __constrainer.#dynamic_name = __value;
+                        true
+                    } else {
+                        false
+                    };
+                });
+                sources.insert(index, Dirty::When(flag));
+            }
+        }
+    }
+
+    // Checks see the pending values, or the stored ones where nothing is pending.
+    let mut checks = TokenStream::new();
+    for identifier in identifiers.values() {
+        if let Identifier::Check(check) = identifier {
+            let mut pending = Vec::new();
+            let mut args = TokenStream::new();
+            for param in &check.params {
+                let param_name = &param.name;
+                pending.push(quote! { self.#param_name.is_some() });
+                let arg = pass(quote! { (*self.#param_name.as_ref().unwrap_or(&__constrainer.#param_name)) }, param.passing);
+                args.append_all(quote! {
+                    #arg,
+                });
+            }
+            let verify = verify(&owner, check, &args);
+            checks.append_all(quote! {
+                if #(#pending)||* {
+                    #verify
+                }
+            });
+        }
+    }
+    let propagation = propagate(graph, &sources, modes, &quote! { __constrainer }, &owner);
+    let propagate_block = propagation.block;
+    let changed_name = changed_name(name);
+    let output = output(graph, quote! { #changed_name });
+    let changed = output_value(graph, changed_value(graph, name, &propagation.changed));
+    let external_names = propagation.external_args.keys().map(|index| identifiers.get_index(*index).unwrap().0);
+    let external_args: TokenStream = propagation.external_args.values().cloned().collect();
+
+    let items = quote! {
+        #vis struct #transaction_name #transaction_generics #where_clause {
+            __constrainer: &'__constrainer mut #name #ty_generics,
+            #pending_fields
+        }
+
+        impl #transaction_impl_generics #transaction_name #transaction_ty_generics #where_clause {
+            #set_fns
+
+            pub fn commit(self, #external_args) -> #output {
+                let __constrainer = self.__constrainer;
+                #checks
+                #store_dynamics
+                #propagate_block
+                #changed
+            }
+        }
+    };
+
+    let ops = quote! {
+        pub fn begin<'__constrainer>(&'__constrainer mut self) -> #transaction_name #transaction_ty_generics {
+            #transaction_name {
+                __constrainer: self,
+                #deliminated_pending
+            }
+        }
+
+        pub fn update<'__constrainer, __F>(&'__constrainer mut self, #external_args __f: __F) -> #output
+        where
+            __F: FnOnce(&mut #transaction_name #transaction_ty_generics),
+        {
+            let mut __transaction = self.begin();
+            __f(&mut __transaction);
+            __transaction.commit(#(#external_names,)*)
+        }
+    };
+
+    Transaction {
+        items,
+        ops,
+    }
+}
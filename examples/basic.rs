@@ -1,11 +1,10 @@
 use constrainer::create_constrainer;
 
-type StaticStr = &'static str;
 create_constrainer!(Constrainer {
     dynamic x f32
     dynamic y f32
     dynamic z f32
-    external ex StaticStr
+    external ex &'static str
     external ex2 f32
     
     constrained xy f32 (x, y) {
@@ -30,7 +29,7 @@ create_constrainer!(Constrainer {
     }
     constrained y_ex f32 (y, ex) {
         println!("Computing yex! {}", ex);
-        y
+        *y
     }
     listener z_reporter (z, ex) {
         println!("z was updated to {}. Also, I hear ex was: {}", z, ex);
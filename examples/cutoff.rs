@@ -0,0 +1,44 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(
+    #[cutoff]
+    #[setters(all)]
+    Thermostat {
+        dynamic temperature f32
+        dynamic target f32
+        constrained heating bool (temperature, target) {
+            println!("Computing heating");
+            temperature < target
+        }
+        constrained power u32 (heating) {
+            println!("Computing power");
+            if *heating {
+                1500
+            } else {
+                0
+            }
+        }
+        listener report (heating, power) {
+            println!("Heating: {}, power: {}", heating, power);
+        }
+    }
+);
+
+fn main() {
+    let mut thermostat = Thermostat::new(18.0, 20.0);
+    assert_eq!(*thermostat.get_power(), 1500);
+
+    println!("\nSetting temperature, still heating");
+    thermostat.set_temperature(19.0);
+    assert!(*thermostat.get_heating());
+
+    println!("\nSetting temperature, no longer heating");
+    thermostat.set_temperature(21.0);
+    assert_eq!(*thermostat.get_power(), 0);
+
+    println!("\nSetting target, still not heating");
+    thermostat.update(|thermostat| {
+        thermostat.set_target(19.0);
+    });
+    println!("{:?} {} {}", thermostat, thermostat.get_temperature(), thermostat.get_target());
+}
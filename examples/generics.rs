@@ -0,0 +1,42 @@
+use constrainer::create_constrainer;
+use std::ops::Mul;
+
+create_constrainer!(Rectangle<T: Copy + Mul<Output = T>> where T: std::fmt::Debug {
+    dynamic width T
+    dynamic height T
+    constrained area T (width, height) {
+        *width**height
+    }
+
+    opgenset (width)
+    opgenset (height)
+});
+
+create_constrainer!(Label<'a> {
+    dynamic text &'a str
+    external suffix &'a str
+    constrained len usize (text, suffix) {
+        text.len()+suffix.len()
+    }
+
+    opgenset (text)
+});
+
+fn main() {
+    let mut rect_f32 = Rectangle::new(2.0f32, 3.0);
+    assert_eq!(*rect_f32.get_area(), 6.0);
+    rect_f32.set_width(4.0);
+    assert_eq!(*rect_f32.get_area(), 12.0);
+
+    let mut rect_f64 = Rectangle::new(2.5f64, 2.0);
+    rect_f64.set_height(4.0);
+    assert_eq!(*rect_f64.get_area(), 10.0);
+    println!("{:?} {:?} {:?} {:?}", rect_f32.get_width(), rect_f32.get_height(), rect_f64.get_width(), rect_f64);
+
+    let owned = String::from("constrained");
+    let mut label = Label::new(&owned, "!");
+    assert_eq!(*label.get_len(), 12);
+    label.set_text("text", "?!");
+    assert_eq!(*label.get_len(), 6);
+    println!("{} {:?}", label.get_text(), label);
+}
@@ -0,0 +1,39 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(
+    #[setters(all)]
+    Signal {
+        dynamic samples u32
+        dynamic frequency f64
+        lazy constrained spectrum f64 (samples, frequency) {
+            println!("Computing spectrum");
+            (0..*samples).map(|sample| (sample as f64*frequency).sin().abs()).sum()
+        }
+        lazy constrained peak f64 (spectrum, samples) {
+            println!("Computing peak");
+            spectrum/f64::from(*samples)
+        }
+        constrained nyquist f64 (frequency) {
+            frequency/2.0
+        }
+    }
+);
+
+fn main() {
+    println!("Initializing");
+    let mut signal = Signal::new(1000, 0.5);
+    assert_eq!(*signal.get_nyquist(), 0.25);
+
+    println!("\nReading peak twice");
+    let peak = *signal.get_peak();
+    assert_eq!(*signal.get_peak(), peak);
+
+    println!("\nSetting samples and frequency");
+    signal.set_samples(10);
+    signal.set_frequency(0.25);
+
+    println!("\nReading spectrum, then peak");
+    let spectrum = *signal.get_spectrum();
+    assert_eq!(*signal.get_peak(), spectrum/10.0);
+    println!("{:?} {} {}", signal, signal.get_samples(), signal.get_frequency());
+}
@@ -7,7 +7,7 @@ fn compute_y(x: f32) -> f32 {
 create_constrainer!(MyConstrainer {
     dynamic x f32
     constrained y f32 (x) {
-        compute_y(x)
+        compute_y(*x)
     }
     constrained z f32 (x, y) {
         x*y
@@ -16,8 +16,9 @@ create_constrainer!(MyConstrainer {
 
 fn main() {
     let constrainer_instance = MyConstrainer::new(2.0);
+    let x = constrainer_instance.get_x();
     let y = constrainer_instance.get_y();
     let z = constrainer_instance.get_z();
     assert_eq!(*y, compute_y(2.0));
-    assert_eq!(*z, y*2.0);
+    assert_eq!(*z, y*x);
 }
\ No newline at end of file
@@ -0,0 +1,48 @@
+use constrainer::create_constrainer;
+
+#[derive(Debug, Clone, Copy)]
+struct Point {
+    x: f32,
+    y: f32,
+}
+
+fn perimeter(points: &[Point]) -> f32 {
+    points.iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| ((b.x-a.x).powi(2)+(b.y-a.y).powi(2)).sqrt())
+        .sum()
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Polygon {
+        dynamic name String
+        dynamic points Vec<Point>
+        dynamic scale f32
+        constrained perimeter f32 (points, move scale) {
+            perimeter(points)*scale
+        }
+        constrained label String (name, perimeter) {
+            format!("{} ({})", name, perimeter)
+        }
+        constrained history Vec<String> (clone label) {
+            vec![label]
+        }
+    }
+);
+
+fn main() {
+    let square = vec![
+        Point { x: 0.0, y: 0.0 },
+        Point { x: 1.0, y: 0.0 },
+        Point { x: 1.0, y: 1.0 },
+        Point { x: 0.0, y: 1.0 },
+    ];
+    let mut polygon = Polygon::new(String::from("square"), square, 2.0);
+    assert_eq!(polygon.get_label(), "square (8)");
+
+    polygon.set_name(String::from("small square"));
+    polygon.set_scale(0.5);
+    assert_eq!(polygon.get_history(), &[String::from("small square (2)")]);
+    println!("{:?} {} {:?} {}", polygon, polygon.get_perimeter(), polygon.get_points(), polygon.get_scale());
+}
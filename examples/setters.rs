@@ -0,0 +1,33 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(
+    /// A box whose volume follows its dimensions.
+    #[setters(all, group(width, height))]
+    Cuboid {
+        dynamic width f32
+        dynamic height f32
+        dynamic depth f32
+        constrained area f32 (width, height) {
+            width*height
+        }
+        constrained volume f32 (area, depth) {
+            area*depth
+        }
+    }
+);
+
+fn main() {
+    let mut cuboid = Cuboid::new(1.0, 2.0, 3.0);
+    assert_eq!(*cuboid.get_volume(), 6.0);
+
+    cuboid.set_width(2.0);
+    assert_eq!(*cuboid.get_area(), 4.0);
+    cuboid.set_depth(0.5);
+    assert_eq!(*cuboid.get_volume(), 2.0);
+    cuboid.set_height(1.0);
+    assert_eq!(*cuboid.get_volume(), 1.0);
+    cuboid.set_width_height(3.0, 3.0);
+    assert_eq!(*cuboid.get_volume(), 4.5);
+
+    println!("{:?} {} {} {}", cuboid, cuboid.get_width(), cuboid.get_height(), cuboid.get_depth());
+}
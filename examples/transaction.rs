@@ -0,0 +1,48 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(Mixer {
+    dynamic red f32
+    dynamic green f32
+    dynamic blue f32
+    dynamic alpha f32
+    external log &'static str
+    constrained brightness f32 (red, green, blue) {
+        println!("Computing brightness");
+        (red+green+blue)/3.0
+    }
+    constrained warmth f32 (red, blue) {
+        println!("Computing warmth");
+        red-blue
+    }
+    listener report (brightness, warmth, log) {
+        println!("{}: brightness {}, warmth {}", log, brightness, warmth);
+    }
+});
+
+fn main() {
+    let mut mixer = Mixer::new(0.25, 0.5, 0.75, 1.0, "new");
+
+    println!("\nSetting red, green and blue at once");
+    mixer.update("update", |mixer| {
+        mixer.set_red(1.0).set_green(0.5);
+        mixer.set_blue(0.0);
+    });
+    assert_eq!(*mixer.get_warmth(), 1.0);
+
+    println!("\nSetting green");
+    let mut transaction = mixer.begin();
+    transaction.set_green(0.5);
+    transaction.commit("commit");
+    assert_eq!(*mixer.get_brightness(), 0.5);
+
+    println!("\nSetting alpha");
+    mixer.update("alpha", |mixer| {
+        mixer.set_alpha(0.5);
+    });
+    assert_eq!(*mixer.get_alpha(), 0.5);
+
+    println!("\nDropping a transaction");
+    mixer.begin().set_red(0.0);
+    assert_eq!(*mixer.get_red(), 1.0);
+    println!("{:?} {} {}", mixer, mixer.get_green(), mixer.get_blue());
+}
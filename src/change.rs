@@ -0,0 +1,16 @@
+/// The previous and current value of a listener param marked `changed`, as in
+/// `listener name (x changed) { ... }`.
+///
+/// When a listener is called by `new`, there is no previous value and `old` is `new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Change<'a, T: ?Sized> {
+    pub old: &'a T,
+    pub new: &'a T,
+}
+
+impl<T: PartialEq + ?Sized> Change<'_, T> {
+    /// Whether the value is different from what it was before.
+    pub fn is_changed(&self) -> bool {
+        self.old != self.new
+    }
+}
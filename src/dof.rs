@@ -0,0 +1,103 @@
+use crate::solve::{
+    jacobian,
+    TOLERANCE,
+};
+
+/// The degrees of freedom of a `solve` item or sketch, as returned by the `dof_report` method
+/// of a constrainer declaring one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DofReport {
+    /// The unknowns of a `solve` item such as `(a, b)`, or the name of a sketch.
+    pub group: &'static str,
+    /// Unknowns minus equations, from the declarations alone. Negative if there are more
+    /// equations than unknowns. Also in the `STRUCTURAL_DOF` constant of the constrainer,
+    /// which doesn't need a solution.
+    pub structural_dof: isize,
+    /// How many independent ways the unknowns can move without breaking an equation, at the
+    /// current solution.
+    pub dof: usize,
+    /// The unknowns that can move. Sketch unknowns are named like `a.x` or `bore.radius`.
+    pub free: Vec<&'static str>,
+    /// The equations implied by the ones before them, which would conflict as soon as they
+    /// stopped agreeing.
+    pub redundant: Vec<&'static str>,
+}
+
+impl DofReport {
+    /// Whether the unknowns are fully determined by equations that are all needed.
+    pub fn is_well_constrained(&self) -> bool {
+        self.dof == 0 && self.redundant.is_empty()
+    }
+
+    #[doc(hidden)]
+    pub fn new<F>(group: &'static str, unknowns: &[&'static str], equations: &[&'static str], solution: &[f64], mut residuals: F) -> Self
+    where
+        F: FnMut(&[f64], &mut [f64]),
+    {
+        let j = jacobian(&mut residuals, solution, equations.len());
+        let scale = j.iter().flatten().fold(1.0f64, |scale, value| scale.max(value.abs()));
+        let tolerance = TOLERANCE.sqrt()*scale;
+
+        // An orthonormal basis of the rows of the Jacobian, in order. A row adding nothing to
+        // the rows before it is a redundant equation.
+        let mut basis: Vec<Vec<f64>> = Vec::new();
+        let mut redundant = Vec::new();
+        for (index, row) in j.into_iter().enumerate() {
+            let rest = reject(row, &basis);
+            let norm = norm(&rest);
+            if norm > tolerance {
+                basis.push(rest.into_iter().map(|value| value/norm).collect());
+            } else {
+                redundant.push(index);
+            }
+        }
+        // An unknown is determined if moving it alone breaks an equation, that is if its axis
+        // is within the rows.
+        let free = (0..unknowns.len())
+            .filter(|unknown| {
+                let mut axis = vec![0.0; unknowns.len()];
+                axis[*unknown] = 1.0;
+                norm(&reject(axis, &basis)) > TOLERANCE.sqrt()
+            })
+            .map(|unknown| unknowns[unknown])
+            .collect();
+
+        Self {
+            group,
+            structural_dof: unknowns.len() as isize - equations.len() as isize,
+            dof: unknowns.len()-basis.len(),
+            free,
+            redundant: equation_names(&redundant, equations),
+        }
+    }
+}
+
+/// The names of the equations at `indices`, each once. Sketch constraints like `fix` are
+/// several equations named alike.
+#[doc(hidden)]
+pub fn equation_names(indices: &[usize], equations: &[&'static str]) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    for index in indices {
+        if !names.contains(&equations[*index]) {
+            names.push(equations[*index]);
+        }
+    }
+    names
+}
+
+// What is left of `vector` once projected out of the orthonormal `basis`, twice for accuracy.
+fn reject(mut vector: Vec<f64>, basis: &[Vec<f64>]) -> Vec<f64> {
+    for _ in 0..2 {
+        for direction in basis {
+            let dot: f64 = vector.iter().zip(direction).map(|(a, b)| a*b).sum();
+            for (value, direction) in vector.iter_mut().zip(direction) {
+                *value -= dot*direction;
+            }
+        }
+    }
+    vector
+}
+
+fn norm(vector: &[f64]) -> f64 {
+    vector.iter().map(|value| value*value).sum::<f64>().sqrt()
+}
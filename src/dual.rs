@@ -0,0 +1,225 @@
+use std::cmp::Ordering;
+use std::ops::{
+    Add,
+    AddAssign,
+    Div,
+    DivAssign,
+    Mul,
+    MulAssign,
+    Neg,
+    Sub,
+    SubAssign,
+};
+
+/// A value along with its derivative with respect to one dynamic, which the bodies of `f64`
+/// constraineds are evaluated over by the `grad_<constrained>_wrt_<dynamic>` methods of a
+/// constrainer declared with `#[gradients]`.
+///
+/// It supports the arithmetic operators, mixed with `f64`s or not, and the usual `f64`
+/// methods. Comparisons only look at the value.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dual {
+    pub value: f64,
+    pub derivative: f64,
+}
+
+impl Dual {
+    pub fn new(value: f64, derivative: f64) -> Self {
+        Self {
+            value,
+            derivative,
+        }
+    }
+
+    /// The value of what the derivative is taken with respect to.
+    pub fn variable(value: f64) -> Self {
+        Self::new(value, 1.0)
+    }
+
+    pub fn constant(value: f64) -> Self {
+        Self::new(value, 0.0)
+    }
+
+    // Applies a function whose derivative at `self.value` is `derivative`.
+    fn chain(self, value: f64, derivative: f64) -> Self {
+        Self::new(value, derivative*self.derivative)
+    }
+
+    pub fn abs(self) -> Self {
+        self.chain(self.value.abs(), self.value.signum())
+    }
+
+    pub fn sqrt(self) -> Self {
+        let value = self.value.sqrt();
+        self.chain(value, 0.5/value)
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        self.chain(self.value.powi(n), n as f64*self.value.powi(n-1))
+    }
+
+    pub fn powf(self, n: f64) -> Self {
+        self.chain(self.value.powf(n), n*self.value.powf(n-1.0))
+    }
+
+    pub fn exp(self) -> Self {
+        let value = self.value.exp();
+        self.chain(value, value)
+    }
+
+    pub fn ln(self) -> Self {
+        self.chain(self.value.ln(), 1.0/self.value)
+    }
+
+    pub fn sin(self) -> Self {
+        self.chain(self.value.sin(), self.value.cos())
+    }
+
+    pub fn cos(self) -> Self {
+        self.chain(self.value.cos(), -self.value.sin())
+    }
+
+    pub fn tan(self) -> Self {
+        let value = self.value.tan();
+        self.chain(value, 1.0 + value*value)
+    }
+
+    pub fn asin(self) -> Self {
+        self.chain(self.value.asin(), 1.0/(1.0 - self.value*self.value).sqrt())
+    }
+
+    pub fn acos(self) -> Self {
+        self.chain(self.value.acos(), -1.0/(1.0 - self.value*self.value).sqrt())
+    }
+
+    pub fn atan(self) -> Self {
+        self.chain(self.value.atan(), 1.0/(1.0 + self.value*self.value))
+    }
+
+    pub fn atan2(self, other: impl Into<Dual>) -> Self {
+        let other = other.into();
+        let denominator = self.value*self.value + other.value*other.value;
+        Self::new(
+            self.value.atan2(other.value),
+            (other.value*self.derivative - self.value*other.derivative)/denominator,
+        )
+    }
+
+    pub fn hypot(self, other: impl Into<Dual>) -> Self {
+        let other = other.into();
+        (self*self + other*other).sqrt()
+    }
+
+    pub fn max(self, other: impl Into<Dual>) -> Self {
+        let other = other.into();
+        if other.value > self.value { other } else { self }
+    }
+
+    pub fn min(self, other: impl Into<Dual>) -> Self {
+        let other = other.into();
+        if other.value < self.value { other } else { self }
+    }
+}
+
+impl From<f64> for Dual {
+    fn from(value: f64) -> Self {
+        Self::constant(value)
+    }
+}
+
+impl PartialEq for Dual {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl PartialEq<f64> for Dual {
+    fn eq(&self, other: &f64) -> bool {
+        self.value == *other
+    }
+}
+
+impl PartialOrd for Dual {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl PartialOrd<f64> for Dual {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        self.value.partial_cmp(other)
+    }
+}
+
+impl Neg for Dual {
+    type Output = Dual;
+
+    fn neg(self) -> Dual {
+        Dual::new(-self.value, -self.derivative)
+    }
+}
+
+impl Neg for &Dual {
+    type Output = Dual;
+
+    fn neg(self) -> Dual {
+        -*self
+    }
+}
+
+// Implements an operator of a number type standing in for `f64` out of `$op`, then between
+// it and `f64`s, whether by value or by reference, the way bodies taking params by reference
+// use them. `f64`s are converted with `From`.
+macro_rules! operator {
+    ($ty:ident, $trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:expr) => {
+        impl $trait for $ty {
+            type Output = $ty;
+
+            fn $method(self, other: $ty) -> $ty {
+                let op: fn($ty, $ty) -> $ty = $op;
+                op(self, other)
+            }
+        }
+
+        impl $assign_trait for $ty {
+            fn $assign_method(&mut self, other: $ty) {
+                *self = $trait::$method(*self, other);
+            }
+        }
+
+        impl $assign_trait<f64> for $ty {
+            fn $assign_method(&mut self, other: f64) {
+                *self = $trait::$method(*self, $ty::from(other));
+            }
+        }
+
+        operator!(@mixed $ty, $trait, $method, $ty, &$ty);
+        operator!(@mixed $ty, $trait, $method, &$ty, $ty);
+        operator!(@mixed $ty, $trait, $method, &$ty, &$ty);
+        operator!(@mixed $ty, $trait, $method, $ty, f64);
+        operator!(@mixed $ty, $trait, $method, &$ty, f64);
+        operator!(@mixed $ty, $trait, $method, $ty, &f64);
+        operator!(@mixed $ty, $trait, $method, &$ty, &f64);
+        operator!(@mixed $ty, $trait, $method, f64, $ty);
+        operator!(@mixed $ty, $trait, $method, f64, &$ty);
+        operator!(@mixed $ty, $trait, $method, &f64, $ty);
+        operator!(@mixed $ty, $trait, $method, &f64, &$ty);
+    };
+    (@mixed $ty:ident, $trait:ident, $method:ident, $left:ty, $right:ty) => {
+        impl $trait<$right> for $left {
+            type Output = $ty;
+
+            #[allow(clippy::op_ref, clippy::clone_on_copy)]
+            fn $method(self, other: $right) -> $ty {
+                $trait::$method($ty::from(self.clone()), $ty::from(other.clone()))
+            }
+        }
+    };
+}
+
+pub(crate) use operator;
+
+operator!(Dual, Add, add, AddAssign, add_assign, |a, b| Dual::new(a.value + b.value, a.derivative + b.derivative));
+operator!(Dual, Sub, sub, SubAssign, sub_assign, |a, b| Dual::new(a.value - b.value, a.derivative - b.derivative));
+operator!(Dual, Mul, mul, MulAssign, mul_assign, |a, b| Dual::new(a.value*b.value, a.derivative*b.value + a.value*b.derivative));
+operator!(Dual, Div, div, DivAssign, div_assign, |a, b| Dual::new(a.value/b.value, (a.derivative*b.value - a.value*b.derivative)/(b.value*b.value)));
@@ -0,0 +1,60 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::SolveError;
+
+/// Any error, as returned by the block of a `try` constrained.
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Returned by `new`, setters and transactions of a constrainer that can fail. Whatever was
+/// set or recomputed before the error is restored, so the constrainer is left as it was.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConstrainerError {
+    /// The block of a `try` constrained returned an error.
+    Compute {
+        constrained: &'static str,
+        source: BoxError,
+    },
+    /// A `check`, or the `where` predicate of a dynamic, didn't hold for the values given.
+    /// `check` is the name of the check or dynamic, or the params of an unnamed check such as
+    /// `(a, b)`.
+    Check {
+        check: &'static str,
+    },
+    /// The equations of a `solve` item or sketch couldn't be satisfied. `unknowns` are the
+    /// unknowns of the `solve` item, such as `(a, b)`, or the name of the sketch, and
+    /// `conflicting` the equations or sketch constraints left unsatisfied.
+    Solve {
+        unknowns: &'static str,
+        conflicting: Vec<&'static str>,
+        source: SolveError,
+    },
+}
+
+impl fmt::Display for ConstrainerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstrainerError::Compute { constrained, source } => write!(f, "failed to compute `{}`: {}", constrained, source),
+            ConstrainerError::Check { check } => write!(f, "check `{}` failed", check),
+            ConstrainerError::Solve { unknowns, conflicting, source } => {
+                write!(f, "failed to solve for `{}`: {}", unknowns, source)?;
+                for (index, equation) in conflicting.iter().enumerate() {
+                    let separator = if index == 0 { ", unsatisfied: " } else { ", " };
+                    write!(f, "{}`{}`", separator, equation)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl Error for ConstrainerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConstrainerError::Compute { source, .. } => Some(source.as_ref()),
+            ConstrainerError::Check { .. } => None,
+            ConstrainerError::Solve { source, .. } => Some(source),
+        }
+    }
+}
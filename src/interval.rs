@@ -0,0 +1,233 @@
+use std::f64::consts::{
+    FRAC_PI_2,
+    PI,
+};
+use std::ops::{
+    Add,
+    AddAssign,
+    Div,
+    DivAssign,
+    Mul,
+    MulAssign,
+    Neg,
+    Sub,
+    SubAssign,
+};
+
+use crate::dual::operator;
+
+/// A closed range of values, which the bodies of `f64` constraineds are evaluated over by the
+/// `interval_view` of a constrainer declared with `#[intervals]`.
+///
+/// It supports the arithmetic operators, mixed with `f64`s or not, and the same `f64` methods
+/// as `Dual`: `abs`, `sqrt`, `powi`, `powf`, `exp`, `ln`, `sin`, `cos`, `tan`, `asin`, `acos`,
+/// `atan`, `atan2`, `hypot`, `max` and `min`. Results are rounded outwards, so they always
+/// contain every value the operation can take over its operands, although they may be wider.
+/// Intervals can't be compared, as a branch taken on one value of an interval doesn't hold for
+/// the others.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl Interval {
+    /// # Panics
+    ///
+    /// If `lo` is greater than `hi`.
+    pub fn new(lo: f64, hi: f64) -> Self {
+        assert!(lo <= hi || lo.is_nan() || hi.is_nan(), "interval from {} to {} is empty", lo, hi);
+        Self {
+            lo,
+            hi,
+        }
+    }
+
+    /// The interval holding `value` alone.
+    pub fn point(value: f64) -> Self {
+        Self::new(value, value)
+    }
+
+    /// `value ± tolerance`.
+    pub fn around(value: f64, tolerance: f64) -> Self {
+        outward(value - tolerance.abs(), value + tolerance.abs())
+    }
+
+    pub fn entire() -> Self {
+        Self::new(f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    pub fn width(&self) -> f64 {
+        self.hi - self.lo
+    }
+
+    pub fn midpoint(&self) -> f64 {
+        self.lo/2.0 + self.hi/2.0
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    pub fn abs(self) -> Self {
+        if self.lo >= 0.0 {
+            self
+        } else if self.hi <= 0.0 {
+            -self
+        } else {
+            Self::new(0.0, self.hi.max(-self.lo))
+        }
+    }
+
+    /// Only the non-negative part of the interval is taken the root of.
+    pub fn sqrt(self) -> Self {
+        outward(self.lo.max(0.0).sqrt(), self.hi.sqrt())
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        if n == 0 {
+            return Self::point(1.0);
+        }
+        if n < 0 {
+            return Self::point(1.0)/self.powi(-n);
+        }
+        if n%2 == 1 {
+            outward(self.lo.powi(n), self.hi.powi(n))
+        } else {
+            let abs = self.abs();
+            outward(abs.lo.powi(n), abs.hi.powi(n))
+        }
+    }
+
+    /// Only the non-negative part of the interval is raised to `n`, unless `n` is an integer.
+    pub fn powf(self, n: f64) -> Self {
+        if n.fract() == 0.0 && n.abs() <= f64::from(i32::MAX) {
+            return self.powi(n as i32);
+        }
+        let lo = self.lo.max(0.0);
+        if n > 0.0 {
+            outward(lo.powf(n), self.hi.powf(n))
+        } else {
+            outward(self.hi.powf(n), lo.powf(n))
+        }
+    }
+
+    pub fn exp(self) -> Self {
+        outward(self.lo.exp(), self.hi.exp()).max(0.0)
+    }
+
+    /// Only the non-negative part of the interval is taken the logarithm of.
+    pub fn ln(self) -> Self {
+        outward(self.lo.max(0.0).ln(), self.hi.ln())
+    }
+
+    pub fn sin(self) -> Self {
+        if self.width() >= 2.0*PI {
+            return Self::new(-1.0, 1.0);
+        }
+        let (a, b) = (self.lo.sin(), self.hi.sin());
+        let lo = if self.reaches(-FRAC_PI_2) { -1.0 } else { a.min(b) };
+        let hi = if self.reaches(FRAC_PI_2) { 1.0 } else { a.max(b) };
+        outward(lo, hi).min(1.0).max(-1.0)
+    }
+
+    pub fn cos(self) -> Self {
+        (self + FRAC_PI_2).sin()
+    }
+
+    pub fn tan(self) -> Self {
+        if self.width() >= PI || self.reaches(FRAC_PI_2) || self.reaches(-FRAC_PI_2) {
+            return Self::entire();
+        }
+        outward(self.lo.tan(), self.hi.tan())
+    }
+
+    /// Only the part of the interval within `[-1, 1]` is taken the arcsine of.
+    pub fn asin(self) -> Self {
+        outward(self.lo.max(-1.0).asin(), self.hi.min(1.0).asin())
+    }
+
+    /// Only the part of the interval within `[-1, 1]` is taken the arccosine of.
+    pub fn acos(self) -> Self {
+        outward(self.hi.min(1.0).acos(), self.lo.max(-1.0).acos())
+    }
+
+    pub fn atan(self) -> Self {
+        outward(self.lo.atan(), self.hi.atan())
+    }
+
+    /// The angle of the points `(other, self)`. It jumps from π to -π across the negative
+    /// x axis, so if `other` reaches below zero while `self` contains zero, this is `[-π, π]`.
+    pub fn atan2(self, other: impl Into<Interval>) -> Self {
+        let other = other.into();
+        if self.contains(0.0) && other.lo <= 0.0 {
+            return outward(-PI, PI);
+        }
+        // Away from the origin and the jump, the extreme angles are at the corners.
+        products(self, other, f64::atan2)
+    }
+
+    pub fn hypot(self, other: impl Into<Interval>) -> Self {
+        (self.powi(2) + other.into().powi(2)).sqrt()
+    }
+
+    pub fn max(self, other: impl Into<Interval>) -> Self {
+        let other = other.into();
+        Self::new(self.lo.max(other.lo), self.hi.max(other.hi))
+    }
+
+    pub fn min(self, other: impl Into<Interval>) -> Self {
+        let other = other.into();
+        Self::new(self.lo.min(other.lo), self.hi.min(other.hi))
+    }
+
+    // Whether the interval contains `angle` plus some multiple of 2π.
+    fn reaches(&self, angle: f64) -> bool {
+        ((self.lo - angle)/(2.0*PI)).ceil() <= ((self.hi - angle)/(2.0*PI)).floor()
+    }
+}
+
+// The interval from `lo` to `hi` widened by one ulp each way to make up for rounding.
+fn outward(lo: f64, hi: f64) -> Interval {
+    Interval::new(lo.next_down(), hi.next_up())
+}
+
+impl From<f64> for Interval {
+    fn from(value: f64) -> Self {
+        Self::point(value)
+    }
+}
+
+impl Neg for Interval {
+    type Output = Interval;
+
+    fn neg(self) -> Interval {
+        Interval::new(-self.hi, -self.lo)
+    }
+}
+
+impl Neg for &Interval {
+    type Output = Interval;
+
+    fn neg(self) -> Interval {
+        -*self
+    }
+}
+
+fn products(a: Interval, b: Interval, op: fn(f64, f64) -> f64) -> Interval {
+    let values = [op(a.lo, b.lo), op(a.lo, b.hi), op(a.hi, b.lo), op(a.hi, b.hi)];
+    let lo = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    outward(lo, hi)
+}
+
+operator!(Interval, Add, add, AddAssign, add_assign, |a, b| outward(a.lo + b.lo, a.hi + b.hi));
+operator!(Interval, Sub, sub, SubAssign, sub_assign, |a, b| outward(a.lo - b.hi, a.hi - b.lo));
+operator!(Interval, Mul, mul, MulAssign, mul_assign, |a, b| products(a, b, |a, b| a*b));
+operator!(Interval, Div, div, DivAssign, div_assign, |a, b| {
+    if b.contains(0.0) {
+        Interval::entire()
+    } else {
+        products(a, b, |a, b| a/b)
+    }
+});
@@ -0,0 +1,88 @@
+//! Geometric primitives of `sketch` items, as returned by the getters of their points, lines
+//! and circles.
+
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self {
+            x,
+            y,
+        }
+    }
+
+    pub fn distance(&self, other: Point) -> f64 {
+        (other.x-self.x).hypot(other.y-self.y)
+    }
+}
+
+/// The infinite line through `start` and `end`, which also bound the segment drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Line {
+    pub start: Point,
+    pub end: Point,
+}
+
+impl Line {
+    pub fn new(start: Point, end: Point) -> Self {
+        Self {
+            start,
+            end,
+        }
+    }
+
+    pub fn length(&self) -> f64 {
+        self.start.distance(self.end)
+    }
+
+    /// The angle of the line from the x axis, in radians.
+    pub fn angle(&self) -> f64 {
+        (self.end.y-self.start.y).atan2(self.end.x-self.start.x)
+    }
+
+    /// The counterclockwise angle from this line to `other`, in radians within `(-π, π]`.
+    pub fn angle_to(&self, other: Line) -> f64 {
+        angle_difference(other.angle(), self.angle())
+    }
+
+    /// The distance from `point` to the line, positive to the left of `start` to `end`.
+    pub fn signed_distance(&self, point: Point) -> f64 {
+        let (dx, dy) = (self.end.x-self.start.x, self.end.y-self.start.y);
+        (dx*(point.y-self.start.y) - dy*(point.x-self.start.x))/dx.hypot(dy)
+    }
+
+    pub fn distance(&self, point: Point) -> f64 {
+        self.signed_distance(point).abs()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: Point, radius: f64) -> Self {
+        Self {
+            center,
+            radius,
+        }
+    }
+}
+
+/// `a - b` wrapped into `(-π, π]`.
+pub fn angle_difference(a: f64, b: f64) -> f64 {
+    let difference = (a-b).rem_euclid(2.0*PI);
+    if difference > PI {
+        difference-2.0*PI
+    } else {
+        difference
+    }
+}
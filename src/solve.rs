@@ -0,0 +1,182 @@
+// The solver behind `solve` items: Levenberg–Marquardt over a finite-difference Jacobian,
+// which turns into Gauss–Newton (Newton–Raphson for as many equations as unknowns) as it gets
+// close to a solution.
+
+use std::error::Error;
+use std::fmt;
+
+/// A solution is accepted once the norm of the residuals is below this.
+pub(crate) const TOLERANCE: f64 = 1e-9;
+const MAX_ITERATIONS: usize = 200;
+
+/// The equations of a `solve` item couldn't be satisfied, either because they have no
+/// solution or because none was found from the starting point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveError {
+    /// The norm of the residuals at the best point found.
+    pub residual: f64,
+    pub iterations: usize,
+    /// The equations left unsatisfied there, by position.
+    pub unsatisfied: Vec<usize>,
+}
+
+impl SolveError {
+    fn new(residuals: &[f64], iterations: usize) -> Self {
+        Self {
+            residual: norm_squared(residuals).sqrt(),
+            iterations,
+            unsatisfied: (0..residuals.len()).filter(|index| residuals[*index].abs() > TOLERANCE || residuals[*index].is_nan()).collect(),
+        }
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "did not converge after {} iterations, residual {}", self.iterations, self.residual)
+    }
+}
+
+impl Error for SolveError {}
+
+/// Finds `unknowns` for which every one of the `equations` residuals written by `residuals` is
+/// zero, starting from their current values. `unknowns` is only updated on success.
+///
+/// Called by the code generated for `solve` items, with the previous solution as the
+/// starting point so that the solution found stays the closest one.
+pub fn solve<F>(unknowns: &mut [f64], equations: usize, mut residuals: F) -> Result<(), SolveError>
+where
+    F: FnMut(&[f64], &mut [f64]),
+{
+    let mut x = unknowns.to_vec();
+    let mut r = vec![0.0; equations];
+    residuals(&x, &mut r);
+    let mut cost = norm_squared(&r);
+    let mut lambda = 1e-3;
+    let mut candidate = vec![0.0; x.len()];
+    let mut candidate_r = vec![0.0; equations];
+
+    for iteration in 0..MAX_ITERATIONS {
+        if !cost.is_finite() {
+            break;
+        }
+        if cost.sqrt() <= TOLERANCE {
+            unknowns.copy_from_slice(&x);
+            return Ok(());
+        }
+
+        let j = jacobian(&mut residuals, &x, equations);
+        // Normal equations: (JᵀJ + λ diag(JᵀJ)) δ = -Jᵀr
+        let n = x.len();
+        let mut a = vec![vec![0.0; n]; n];
+        let mut g = vec![0.0; n];
+        for (row, residual) in j.iter().zip(&r) {
+            for k in 0..n {
+                g[k] += row[k]*residual;
+                for l in 0..n {
+                    a[k][l] += row[k]*row[l];
+                }
+            }
+        }
+
+        let mut improved = false;
+        while lambda < 1e12 {
+            let mut damped = a.clone();
+            for (k, row) in damped.iter_mut().enumerate() {
+                row[k] += lambda*a[k][k].max(1e-12);
+            }
+            let step = match solve_linear(damped, g.iter().map(|g| -g).collect()) {
+                Some(step) => step,
+                None => {
+                    lambda *= 10.0;
+                    continue;
+                },
+            };
+            for k in 0..n {
+                candidate[k] = x[k]+step[k];
+            }
+            residuals(&candidate, &mut candidate_r);
+            let candidate_cost = norm_squared(&candidate_r);
+            if candidate_cost < cost {
+                std::mem::swap(&mut x, &mut candidate);
+                std::mem::swap(&mut r, &mut candidate_r);
+                let step_size = norm_squared(&step).sqrt();
+                cost = candidate_cost;
+                lambda = (lambda/10.0).max(1e-12);
+                improved = step_size > f64::EPSILON*(1.0+norm_squared(&x).sqrt());
+                if !improved && cost.sqrt() <= TOLERANCE {
+                    improved = true;
+                }
+                break;
+            }
+            lambda *= 10.0;
+        }
+        if !improved {
+            return Err(SolveError::new(&r, iteration+1));
+        }
+    }
+
+    if cost.sqrt() <= TOLERANCE {
+        unknowns.copy_from_slice(&x);
+        return Ok(());
+    }
+    Err(SolveError::new(&r, MAX_ITERATIONS))
+}
+
+// The partial derivatives of every residual with respect to every unknown at `x`, one row per
+// residual, by central differences.
+pub(crate) fn jacobian<F>(residuals: &mut F, x: &[f64], equations: usize) -> Vec<Vec<f64>>
+where
+    F: FnMut(&[f64], &mut [f64]),
+{
+    let mut j = vec![vec![0.0; x.len()]; equations];
+    let mut shifted = x.to_vec();
+    let mut forward = vec![0.0; equations];
+    let mut backward = vec![0.0; equations];
+    for k in 0..x.len() {
+        let h = 1e-6*x[k].abs().max(1.0);
+        shifted[k] = x[k]+h;
+        residuals(&shifted, &mut forward);
+        shifted[k] = x[k]-h;
+        residuals(&shifted, &mut backward);
+        shifted[k] = x[k];
+        for (row, (forward, backward)) in j.iter_mut().zip(forward.iter().zip(&backward)) {
+            row[k] = (forward-backward)/(2.0*h);
+        }
+    }
+    j
+}
+
+fn norm_squared(values: &[f64]) -> f64 {
+    values.iter().map(|value| value*value).sum()
+}
+
+// Gaussian elimination with partial pivoting. `None` if `a` is singular.
+fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|i, j| a[*i][column].abs().total_cmp(&a[*j][column].abs()))?;
+        if a[pivot][column].abs() < 1e-300 {
+            return None;
+        }
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+        let (above, below) = a.split_at_mut(column+1);
+        let pivot_row = &above[column];
+        for (offset, row) in below.iter_mut().enumerate() {
+            let factor = row[column]/pivot_row[column];
+            if factor == 0.0 {
+                continue;
+            }
+            for (value, pivot_value) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                *value -= factor*pivot_value;
+            }
+            b[column+1+offset] -= factor*b[column];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row+1..n).map(|k| a[row][k]*x[k]).sum();
+        x[row] = (b[row]-sum)/a[row][row];
+    }
+    Some(x)
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+/// Identifies a subscriber added at runtime with one of the `subscribe_<name>` methods of a
+/// constrainer declared with `#[subscriptions]`, so that it can be passed to `unsubscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionId(u64);
+
+impl SubscriptionId {
+    #[doc(hidden)]
+    pub fn first() -> Self {
+        SubscriptionId(0)
+    }
+
+    // Returns this id and moves on to the next one.
+    #[doc(hidden)]
+    pub fn take_next(&mut self) -> Self {
+        let id = *self;
+        self.0 += 1;
+        id
+    }
+}
+
+/// A closure called with the new value of what it subscribed to.
+pub type Subscriber<T> = Box<dyn FnMut(&T)>;
+
+/// The closures subscribed to a single dynamic or constrained, called with its current value
+/// every time it changes.
+pub struct Subscribers<T: ?Sized> {
+    subscribers: Vec<(SubscriptionId, Subscriber<T>)>,
+}
+
+impl<T: ?Sized> Subscribers<T> {
+    pub fn new() -> Self {
+        Self {
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    pub fn subscribe(&mut self, id: SubscriptionId, subscriber: Subscriber<T>) {
+        self.subscribers.push((id, subscriber));
+    }
+
+    /// Whether a subscriber with this id was found and removed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let len = self.subscribers.len();
+        self.subscribers.retain(|(subscriber_id, _)| *subscriber_id != id);
+        self.subscribers.len() != len
+    }
+
+    /// Calls every subscriber, in the order they subscribed.
+    pub fn notify(&mut self, value: &T) {
+        for (_, subscriber) in &mut self.subscribers {
+            subscriber(value);
+        }
+    }
+}
+
+impl<T: ?Sized> Default for Subscribers<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Subscribers<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Subscribers")
+            .field("len", &self.subscribers.len())
+            .finish()
+    }
+}
@@ -0,0 +1,99 @@
+use constrainer::{
+    constrainer,
+    ConstrainerError,
+};
+use std::cell::RefCell;
+
+#[constrainer]
+#[setters(all)]
+/// A rectangle drawn a number of times.
+#[derive(Clone, Debug)]
+struct Shape {
+    #[dynamic]
+    width: f32,
+    #[dynamic]
+    height: f32,
+    /// How many times the shape is drawn.
+    #[dynamic]
+    copies: u32,
+    #[external]
+    log: &RefCell<Vec<f32>>,
+    /// The area of one copy.
+    #[constrained(width, height)]
+    area: f32,
+    #[constrained(deps = [area, move copies, log], with = scale)]
+    total: f32,
+    #[constrained(deps = [width, height], with = ratio, fallible)]
+    aspect: f32,
+    #[constrained(deps = [total], with = Self::half, lazy)]
+    half: f32,
+}
+
+fn compute_area(width: &f32, height: &f32) -> f32 {
+    width*height
+}
+
+fn scale(area: &f32, copies: u32, log: &&RefCell<Vec<f32>>) -> f32 {
+    let total = area*copies as f32;
+    log.borrow_mut().push(total);
+    total
+}
+
+fn ratio(width: &f32, height: &f32) -> Result<f32, String> {
+    if *height == 0.0 {
+        Err("no height".to_string())
+    } else {
+        Ok(width/height)
+    }
+}
+
+impl Shape {
+    fn half(total: &f32) -> f32 {
+        total/2.0
+    }
+}
+
+#[test]
+fn computed_by_functions() {
+    let log = RefCell::new(Vec::new());
+    let shape = Shape::new(2.0, 3.0, 2, &log).unwrap();
+    assert_eq!(*shape.get_area(), 6.0);
+    assert_eq!(*shape.get_total(), 12.0);
+    assert_eq!(*shape.get_aspect(), 2.0/3.0);
+    assert_eq!(*shape.get_half(), 6.0);
+}
+
+#[test]
+fn setters_recompute() {
+    let log = RefCell::new(Vec::new());
+    let mut shape = Shape::new(2.0, 3.0, 2, &log).unwrap();
+    let changed = shape.set_copies(3, &log).unwrap();
+    assert!(changed.copies() && changed.total());
+    assert!(!changed.area() && !changed.aspect());
+    assert_eq!(*shape.get_total(), 18.0);
+    assert_eq!(*shape.get_half(), 9.0);
+    assert_eq!(*log.borrow(), vec![12.0, 18.0]);
+}
+
+#[test]
+fn fallible_functions() {
+    let log = RefCell::new(Vec::new());
+    let mut shape = Shape::new(2.0, 3.0, 2, &log).unwrap();
+    match shape.set_height(0.0, &log) {
+        Err(ConstrainerError::Compute { constrained, .. }) => assert_eq!(constrained, "aspect"),
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    }
+    assert_eq!(*shape.get_height(), 3.0);
+}
+
+#[test]
+fn derives_are_kept() {
+    let log = RefCell::new(Vec::new());
+    let shape = Shape::new(2.0, 3.0, 2, &log).unwrap();
+    let copy = shape.clone();
+    assert_eq!(*copy.get_total(), 12.0);
+    assert_eq!(
+        format!("{:?}", copy),
+        "Shape { width: 2.0, height: 3.0, copies: 2, area: 6.0, total: 12.0, aspect: 0.6666667, half: OnceCell(<uninit>) }",
+    );
+}
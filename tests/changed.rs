@@ -0,0 +1,78 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(
+    #[setters(all, group(width, height))]
+    Cuboid {
+        dynamic width f32
+        dynamic height f32
+        dynamic depth f32
+        constrained area f32 (width, height) {
+            width*height
+        }
+        constrained volume f32 (area, depth) {
+            area*depth
+        }
+    }
+);
+
+#[test]
+fn setter_returns_set_and_recomputed() {
+    let mut cuboid = Cuboid::new(2.0, 3.0, 4.0);
+    let changed = cuboid.set_depth(1.0);
+    assert!(changed.depth() && changed.volume());
+    assert!(!changed.width() && !changed.height() && !changed.area());
+
+    let changed = cuboid.set_width_height(1.0, 1.0);
+    assert!(changed.width() && changed.height() && changed.area() && changed.volume());
+    assert!(!changed.depth());
+}
+
+#[test]
+fn transaction_returns_what_was_set() {
+    let mut cuboid = Cuboid::new(2.0, 3.0, 4.0);
+    let changed = cuboid.update(|transaction| {
+        transaction.set_depth(2.0);
+    });
+    assert!(changed.depth() && changed.volume());
+    assert!(!changed.area());
+
+    let changed = cuboid.begin().commit();
+    assert!(changed.is_empty());
+}
+
+#[test]
+fn changes_combine() {
+    let mut cuboid = Cuboid::new(2.0, 3.0, 4.0);
+    let mut changed = cuboid.set_depth(1.0);
+    changed |= cuboid.set_width(1.0);
+    assert!(changed.width() && changed.area() && changed.depth() && changed.volume());
+    assert!(!changed.height());
+    assert_eq!(format!("{:?}", changed), "CuboidChanged {width, depth, area, volume}");
+}
+
+create_constrainer!(
+    #[cutoff]
+    #[setters(all)]
+    Rounded {
+        dynamic x f32
+        constrained rounded i32 (x) {
+            x.round() as i32
+        }
+        constrained doubled i32 (rounded) {
+            rounded*2
+        }
+    }
+);
+
+#[test]
+fn cutoff_leaves_out_everything_after_unchanged_constraineds() {
+    let mut rounded = Rounded::new(1.0);
+    let first = rounded.set_x(1.2);
+    assert!(first.x() && !first.rounded() && !first.doubled());
+    let second = rounded.set_x(1.6);
+    assert!(second.x() && second.rounded() && second.doubled());
+    assert_eq!(*rounded.get_doubled(), 4);
+
+    let combined = first | second;
+    assert!(combined.rounded() && combined.doubled());
+}
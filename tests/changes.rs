@@ -0,0 +1,117 @@
+use constrainer::{
+    create_constrainer,
+    Change,
+};
+use std::cell::RefCell;
+
+#[derive(Debug, PartialEq)]
+enum Call {
+    Area(f32, f32),
+    Width(f32, f32, f32),
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Rectangle {
+        dynamic width f32
+        dynamic height f32
+        external log &RefCell<Vec<Call>>
+        constrained area f32 (width, height) {
+            width*height
+        }
+        listener area_changed (area changed, log) {
+            log.borrow_mut().push(Call::Area(*area.old, *area.new));
+        }
+        listener width_changed (width changed, height, log) {
+            log.borrow_mut().push(Call::Width(*width.old, *width.new, *height));
+        }
+    }
+);
+
+#[test]
+fn new_passes_no_change() {
+    let log = RefCell::new(Vec::new());
+    Rectangle::new(2.0, 3.0, &log);
+    assert_eq!(log.into_inner(), vec![
+        Call::Area(6.0, 6.0),
+        Call::Width(2.0, 2.0, 3.0),
+    ]);
+}
+
+#[test]
+fn setter_passes_previous_values() {
+    let log = RefCell::new(Vec::new());
+    let mut rectangle = Rectangle::new(2.0, 3.0, &log);
+    log.borrow_mut().clear();
+
+    rectangle.set_width(4.0, &log);
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Area(6.0, 12.0),
+        Call::Width(2.0, 4.0, 3.0),
+    ]);
+
+    rectangle.set_height(1.0, &log);
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Area(12.0, 4.0),
+        Call::Width(4.0, 4.0, 1.0),
+    ]);
+}
+
+#[test]
+fn transaction_passes_values_from_before_commit() {
+    let log = RefCell::new(Vec::new());
+    let mut rectangle = Rectangle::new(2.0, 3.0, &log);
+    log.borrow_mut().clear();
+
+    rectangle.update(&log, |transaction| {
+        transaction.set_width(3.0).set_width(5.0).set_height(2.0);
+    });
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Area(6.0, 10.0),
+        Call::Width(2.0, 5.0, 2.0),
+    ]);
+
+    rectangle.update(&log, |transaction| {
+        transaction.set_height(4.0);
+    });
+    assert_eq!(log.into_inner(), vec![
+        Call::Area(10.0, 20.0),
+        Call::Width(5.0, 5.0, 4.0),
+    ]);
+}
+
+create_constrainer!(
+    #[cutoff]
+    #[setters(all)]
+    Clamp {
+        dynamic value f32
+        dynamic limit f32
+        external log &RefCell<Vec<(f32, f32, f32)>>
+        constrained clamped f32 (value, limit) {
+            value.min(*limit)
+        }
+        listener report (clamped changed, limit, log) {
+            log.borrow_mut().push((*clamped.old, *clamped.new, *limit));
+        }
+    }
+);
+
+#[test]
+fn cutoff_passes_unchanged_params_as_unchanged() {
+    let log = RefCell::new(Vec::new());
+    let mut clamp = Clamp::new(5.0, 10.0, &log);
+    log.borrow_mut().clear();
+
+    // Called for the new limit, with `clamped` recomputed to the same value.
+    clamp.set_limit(20.0, &log);
+    clamp.set_value(30.0, &log);
+    // Not called, `clamped` is cut off at the limit.
+    clamp.set_value(40.0, &log);
+    assert_eq!(log.into_inner(), vec![(5.0, 5.0, 20.0), (5.0, 20.0, 20.0)]);
+}
+
+#[test]
+fn change_compares_old_and_new() {
+    assert!(Change { old: &1, new: &2 }.is_changed());
+    assert!(!Change { old: &1, new: &1 }.is_changed());
+}
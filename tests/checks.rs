@@ -0,0 +1,69 @@
+use constrainer::{
+    create_constrainer,
+    ConstrainerError,
+};
+
+create_constrainer!(
+    #[setters(all, group(min, max))]
+    Range {
+        dynamic min f32
+        dynamic max f32
+        dynamic width f32 where *width > 0.0
+        constrained span f32 (min, max) {
+            max-min
+        }
+        check ordered (min, max) {
+            min <= max
+        }
+        check (move width, max) {
+            width <= *max
+        }
+    }
+);
+
+fn failed_check<T: std::fmt::Debug>(result: Result<T, ConstrainerError>) -> &'static str {
+    match result.unwrap_err() {
+        ConstrainerError::Check { check } => check,
+        error => panic!("unexpected error {}", error),
+    }
+}
+
+#[test]
+fn new_verifies_checks() {
+    assert!(Range::new(0.0, 2.0, 1.0).is_ok());
+    assert_eq!(failed_check(Range::new(3.0, 2.0, 1.0)), "ordered");
+    assert_eq!(failed_check(Range::new(0.0, 2.0, 0.0)), "width");
+    assert_eq!(Range::new(0.0, 2.0, -1.0).unwrap_err().to_string(), "check `width` failed");
+}
+
+#[test]
+fn setters_verify_checks_before_storing() {
+    let mut range = Range::new(0.0, 2.0, 1.0).unwrap();
+    assert_eq!(failed_check(range.set_min(3.0)), "ordered");
+    assert_eq!(failed_check(range.set_width(-1.0)), "width");
+    assert_eq!((*range.get_min(), *range.get_width(), *range.get_span()), (0.0, 1.0, 2.0));
+
+    range.set_min_max(4.0, 6.0).unwrap();
+    assert_eq!(*range.get_span(), 2.0);
+}
+
+#[test]
+fn unnamed_checks_are_named_by_their_params() {
+    let mut range = Range::new(0.0, 2.0, 1.0).unwrap();
+    assert_eq!(failed_check(range.set_width(3.0)), "(width, max)");
+}
+
+#[test]
+fn transactions_verify_pending_values() {
+    let mut range = Range::new(0.0, 2.0, 1.0).unwrap();
+    let result = range.update(|transaction| {
+        transaction.set_min(3.0);
+    });
+    assert_eq!(failed_check(result), "ordered");
+    assert_eq!(*range.get_min(), 0.0);
+
+    range.update(|transaction| {
+        transaction.set_max(5.0).set_min(3.0);
+    }).unwrap();
+    assert_eq!(*range.get_span(), 2.0);
+}
@@ -0,0 +1,126 @@
+use constrainer::{
+    create_constrainer,
+    ConstrainerError,
+};
+
+create_constrainer!(
+    #[setters(all)]
+    Equations {
+        dynamic r f64
+        dynamic sum f64
+        dynamic limit f64
+        solve (a, b) {
+            a*a + b*b == r*r;
+            a == 2.0*b
+        }
+        solve (x, y) {
+            x + y == sum
+        }
+        solve (z) {
+            z == 1.0;
+            z == limit
+        }
+    }
+);
+
+#[test]
+fn reports_every_group() {
+    let equations = Equations::new(5.0, 1.0, 1.0).unwrap();
+    let reports = equations.dof_report();
+    let groups: Vec<&str> = reports.iter().map(|report| report.group).collect();
+    assert_eq!(groups, vec!["(a, b)", "(x, y)", "(z)"]);
+
+    assert!(reports[0].is_well_constrained());
+    assert_eq!((reports[0].structural_dof, reports[0].dof), (0, 0));
+
+    assert_eq!((reports[1].structural_dof, reports[1].dof), (1, 1));
+    assert_eq!(reports[1].free, vec!["x", "y"]);
+    assert!(reports[1].redundant.is_empty());
+
+    assert_eq!((reports[2].structural_dof, reports[2].dof), (-1, 0));
+    assert!(reports[2].free.is_empty());
+    assert_eq!(reports[2].redundant, vec!["z == limit"]);
+}
+
+#[test]
+fn structural_dof_is_known_without_an_instance() {
+    assert_eq!(Equations::STRUCTURAL_DOF, &[("(a, b)", 0), ("(x, y)", 1), ("(z)", -1)]);
+}
+
+#[test]
+fn conflicting_equations_are_named() {
+    let mut equations = Equations::new(5.0, 1.0, 1.0).unwrap();
+    match equations.set_limit(2.0) {
+        Err(ConstrainerError::Solve { unknowns, conflicting, .. }) => {
+            assert_eq!(unknowns, "(z)");
+            assert_eq!(conflicting, vec!["z == 1.0", "z == limit"]);
+        },
+        result => panic!("expected a solve error, got {:?}", result),
+    }
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Frame {
+        dynamic width f64
+        dynamic height f64
+        external offset &f64
+        sketch rectangle {
+            point a (0.0, 0.0)
+            point b (3.0, 0.1)
+            point c (3.2, 2.0)
+            point d (0.1, 2.1)
+            line bottom (a, b)
+            line right (b, c)
+            line top (c, d)
+            line left (d, a)
+            fix (a) == (**offset, 0.0)
+            horizontal (bottom)
+            distance (a, b) == width
+            vertical (right)
+            distance (b, c) == height
+            horizontal (top)
+            vertical (left)
+            parallel (top, bottom)
+        }
+        sketch loose {
+            point p (1.0, 1.0)
+            point q (2.0, 1.0)
+            distance (p, q) == width
+        }
+    }
+);
+
+#[test]
+fn sketches_are_reported() {
+    let frame = Frame::new(4.0, 2.0, &0.0).unwrap();
+    let reports = frame.dof_report(&0.0);
+
+    assert_eq!(reports[0].group, "rectangle");
+    assert_eq!((reports[0].structural_dof, reports[0].dof), (-1, 0));
+    assert!(reports[0].free.is_empty());
+    assert_eq!(reports[0].redundant, vec!["parallel (top, bottom)"]);
+
+    assert_eq!(reports[1].group, "loose");
+    assert_eq!((reports[1].structural_dof, reports[1].dof), (3, 3));
+    assert_eq!(reports[1].free, vec!["p.x", "p.y", "q.x", "q.y"]);
+}
+
+create_constrainer!(
+    Segment {
+        dynamic length f64
+        sketch segment {
+            point p (0.0, 0.0)
+            point q (1.0, 0.0)
+            fix (p) == (0.0, 0.0)
+            fix (q) == (1.0, 0.0)
+            distance (p, q) == length
+        }
+    }
+);
+
+#[test]
+fn structural_dof_of_sketches_that_cannot_be_solved() {
+    assert!(Segment::new(2.0).is_err());
+    assert_eq!(Segment::STRUCTURAL_DOF, &[("segment", -1)]);
+}
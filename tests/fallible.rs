@@ -0,0 +1,147 @@
+use constrainer::{
+    create_constrainer,
+    ConstrainerError,
+};
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+struct DivisionByZero;
+
+impl fmt::Display for DivisionByZero {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("division by zero")
+    }
+}
+
+impl Error for DivisionByZero {}
+
+create_constrainer!(
+    #[setters(all, group(a, b))]
+    Ratio {
+        dynamic a f32
+        dynamic b f32
+        dynamic scale f32
+        external log &RefCell<Vec<f32>>
+        constrained scaled f32 (a, scale) {
+            a*scale
+        }
+        constrained ratio f32 (scaled, b) try {
+            if *b == 0.0 {
+                Err(DivisionByZero)
+            } else {
+                Ok(scaled/b)
+            }
+        }
+        lazy constrained inverse f32 (ratio) {
+            1.0/ratio
+        }
+        listener report (ratio, log) {
+            log.borrow_mut().push(*ratio);
+        }
+    }
+);
+
+fn assert_unchanged(ratio: &Ratio) {
+    assert_eq!((*ratio.get_a(), *ratio.get_b(), *ratio.get_scale()), (2.0, 4.0, 1.0));
+    assert_eq!((*ratio.get_scaled(), *ratio.get_ratio(), *ratio.get_inverse()), (2.0, 0.5, 2.0));
+}
+
+#[test]
+fn new_returns_errors() {
+    let log = RefCell::new(Vec::new());
+    let error = Ratio::new(1.0, 0.0, 1.0, &log).unwrap_err();
+    assert!(matches!(error, ConstrainerError::Compute { constrained: "ratio", .. }));
+    assert_eq!(error.to_string(), "failed to compute `ratio`: division by zero");
+    assert!(error.source().unwrap().is::<DivisionByZero>());
+    assert!(log.into_inner().is_empty());
+}
+
+#[test]
+fn failed_setter_restores_everything() {
+    let log = RefCell::new(Vec::new());
+    let mut ratio = Ratio::new(2.0, 4.0, 1.0, &log).unwrap();
+    assert_unchanged(&ratio);
+    log.borrow_mut().clear();
+
+    assert!(ratio.set_a_b(8.0, 0.0, &log).is_err());
+    assert_unchanged(&ratio);
+    assert!(ratio.set_b(0.0, &log).is_err());
+    assert_unchanged(&ratio);
+    assert!(log.borrow().is_empty());
+
+    let changed = ratio.set_scale(2.0, &log).unwrap();
+    assert!(changed.scaled() && changed.ratio() && changed.inverse());
+    assert_eq!(*ratio.get_inverse(), 1.0);
+    assert_eq!(log.into_inner(), vec![1.0]);
+}
+
+#[test]
+fn failed_transaction_restores_everything() {
+    let log = RefCell::new(Vec::new());
+    let mut ratio = Ratio::new(2.0, 4.0, 1.0, &log).unwrap();
+    log.borrow_mut().clear();
+
+    let result = ratio.update(&log, |transaction| {
+        transaction.set_a(4.0).set_scale(3.0).set_b(0.0);
+    });
+    assert!(result.is_err());
+    assert_unchanged(&ratio);
+    assert!(log.into_inner().is_empty());
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Sqrt {
+        dynamic x f64
+        constrained root f64 (x) try {
+            if *x < 0.0 {
+                return Err("negative");
+            }
+            Ok(x.sqrt())
+        }
+    }
+);
+
+#[test]
+fn error_can_be_any_boxable_error() {
+    let mut sqrt = Sqrt::new(4.0).unwrap();
+    assert_eq!(sqrt.set_x(-1.0).unwrap_err().to_string(), "failed to compute `root`: negative");
+    assert_eq!(*sqrt.get_root(), 2.0);
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Bounded {
+        dynamic x f64
+        lazy constrained double f64 (x) {
+            x*2.0
+        }
+        constrained limited f64 (double) try {
+            if *double > 10.0 {
+                Err("too large")
+            } else {
+                Ok(*double)
+            }
+        }
+    }
+);
+
+#[test]
+fn failed_updates_restore_lazy_constraineds_read_by_fallible_ones() {
+    let mut bounded = Bounded::new(1.0).unwrap();
+    assert!(bounded.set_x(100.0).is_err());
+    assert_eq!(*bounded.get_x(), 1.0);
+    assert_eq!(*bounded.get_double(), 2.0);
+    assert_eq!(*bounded.get_limited(), 2.0);
+
+    assert!(bounded.update(|transaction| {
+        transaction.set_x(50.0);
+    }).is_err());
+    assert_eq!(*bounded.get_double(), 2.0);
+
+    bounded.set_x(3.0).unwrap();
+    assert_eq!(*bounded.get_double(), 6.0);
+    assert_eq!(*bounded.get_limited(), 6.0);
+}
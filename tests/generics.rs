@@ -0,0 +1,66 @@
+use constrainer::create_constrainer;
+use std::ops::Mul;
+
+create_constrainer!(
+    #[setters(all)]
+    Rectangle<T: Copy + Mul<Output = T>> where T: std::fmt::Debug {
+        dynamic width T
+        dynamic height T
+        constrained area T (width, height) {
+            *width**height
+        }
+    }
+);
+
+#[test]
+fn type_parameters() {
+    let mut integers = Rectangle::new(2u32, 3);
+    integers.set_width(4);
+    assert_eq!(*integers.get_area(), 12);
+
+    let mut floats = Rectangle::new(2.5f64, 2.0);
+    floats.set_height(4.0);
+    assert_eq!(*floats.get_area(), 10.0);
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Label<'a> {
+        dynamic text &'a str
+        external suffix &'a str
+        constrained len usize (text, suffix) {
+            text.len()+suffix.len()
+        }
+    }
+);
+
+#[test]
+fn lifetimes() {
+    let owned = String::from("constrained");
+    let mut label = Label::new(&owned, "!");
+    assert_eq!(*label.get_len(), 12);
+    let text = owned[..4].to_string();
+    label.set_text(&text, "?!");
+    assert_eq!((*label.get_text(), *label.get_len()), ("cons", 6));
+}
+
+// `S` only appears in an external, so the struct needs a marker for it.
+create_constrainer!(
+    #[setters(all)]
+    Scaled<S: Into<f64> + Copy> {
+        dynamic value f64
+        external scale S
+        constrained scaled f64 (value, move scale) {
+            value*scale.into()
+        }
+    }
+);
+
+#[test]
+fn parameters_only_used_by_externals() {
+    let mut scaled = Scaled::new(2.0, 3u8);
+    assert_eq!(*scaled.get_scaled(), 6.0);
+    scaled.set_value(1.5, 2u8);
+    assert_eq!(*scaled.get_scaled(), 3.0);
+    assert_eq!(format!("{:?}", scaled), "Scaled { value: 1.5, scaled: 3.0 }");
+}
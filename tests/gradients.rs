@@ -0,0 +1,72 @@
+use constrainer::{
+    create_constrainer,
+    Dual,
+};
+
+create_constrainer!(
+    #[gradients]
+    #[setters(all)]
+    Box3 {
+        dynamic width f64
+        dynamic height f64
+        dynamic depth f64
+        dynamic label &'static str
+        external scale &f64
+        constrained area f64 (width, height) {
+            width*height
+        }
+        constrained volume f64 (area, depth) {
+            area*depth
+        }
+        constrained diagonal f64 (width, height) {
+            width.hypot(*height)
+        }
+        constrained scaled f64 (volume, scale) {
+            volume * *scale
+        }
+        constrained clamped f64 (area) {
+            area.min(10.0)
+        }
+        lazy constrained angle f64 (width, height) {
+            height.atan2(*width).sin()
+        }
+        constrained title String (label, width) {
+            format!("{} {}", label, width)
+        }
+    }
+);
+
+fn close(a: f64, b: f64) -> bool {
+    (a-b).abs() < 1e-12
+}
+
+#[test]
+fn gradients_follow_dependencies() {
+    let shape = Box3::new(2.0, 3.0, 4.0, "box", &2.0);
+    assert!(close(shape.grad_area_wrt_width(), 3.0));
+    assert!(close(shape.grad_area_wrt_height(), 2.0));
+    assert!(close(shape.grad_volume_wrt_width(), 12.0));
+    assert!(close(shape.grad_volume_wrt_depth(), 6.0));
+    assert!(close(shape.grad_diagonal_wrt_width(), 2.0/13f64.sqrt()));
+    assert!(close(shape.grad_scaled_wrt_height(&2.0), 16.0));
+    assert!(close(shape.grad_angle_wrt_width(), -6.0/13f64.powf(1.5)));
+}
+
+#[test]
+fn gradients_use_the_current_values() {
+    let mut shape = Box3::new(2.0, 3.0, 4.0, "box", &2.0);
+    assert!(close(shape.grad_clamped_wrt_width(), 3.0));
+    shape.set_width(5.0, &2.0);
+    assert!(close(shape.grad_clamped_wrt_width(), 0.0));
+    assert!(close(shape.grad_volume_wrt_height(), 20.0));
+}
+
+#[test]
+fn duals_differentiate() {
+    let x = Dual::variable(2.0);
+    let y = (x*x + 3.0*x).sqrt();
+    assert!(close(y.value, 10f64.sqrt()));
+    assert!(close(y.derivative, 7.0/(2.0*10f64.sqrt())));
+    assert!(close((1.0/x).derivative, -0.25));
+    assert!(close((x.exp()*x.ln()).derivative, 2f64.exp()*(2f64.ln() + 0.5)));
+}
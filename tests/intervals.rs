@@ -0,0 +1,118 @@
+use constrainer::{
+    create_constrainer,
+    Interval,
+};
+
+create_constrainer!(
+    #[intervals]
+    Part {
+        dynamic width f64
+        dynamic height f64
+        dynamic name &'static str
+        external clearance &f64
+        constrained area f64 (width, height) {
+            width*height
+        }
+        constrained fit f64 (width, clearance) {
+            width - **clearance
+        }
+        constrained ratio f64 (width, height) {
+            width/height
+        }
+        constrained diagonal f64 (width, height) {
+            width.hypot(*height)
+        }
+        constrained label String (name, area) {
+            format!("{}: {}", name, area)
+        }
+    }
+);
+
+fn contains(interval: &Interval, lo: f64, hi: f64) -> bool {
+    interval.lo <= lo && hi <= interval.hi && interval.width() < (hi - lo) + 1e-9
+}
+
+#[test]
+fn ranges_are_propagated() {
+    let view = Part::interval_view(Interval::new(1.9, 2.1), Interval::around(3.0, 0.1), &0.5);
+    assert_eq!(*view.get_width(), Interval::new(1.9, 2.1));
+    assert!(contains(view.get_area(), 1.9*2.9, 2.1*3.1));
+    assert!(contains(view.get_fit(), 1.4, 1.6));
+    assert!(contains(view.get_ratio(), 1.9/3.1, 2.1/2.9));
+    assert!(contains(view.get_diagonal(), 1.9f64.hypot(2.9), 2.1f64.hypot(3.1)));
+}
+
+#[test]
+fn points_give_the_values() {
+    let part = Part::new(2.0, 3.0, "part", &0.5);
+    let view = Part::interval_view(Interval::point(*part.get_width()), Interval::point(*part.get_height()), &0.5);
+    assert!(view.get_area().contains(*part.get_area()));
+    assert!(view.get_ratio().contains(*part.get_ratio()));
+    assert!(view.get_fit().contains(*part.get_fit()));
+    assert!(view.get_diagonal().contains(*part.get_diagonal()));
+    assert!(view.get_area().width() < 1e-12);
+    assert_eq!(part.get_label(), "part: 6");
+    assert_eq!(*part.get_name(), "part");
+}
+
+#[test]
+fn intervals_are_guaranteed() {
+    let x = Interval::new(-1.0, 2.0);
+    assert_eq!(x.abs(), Interval::new(0.0, 2.0));
+    assert!(contains(&x.powi(2), 0.0, 4.0));
+    assert!(contains(&(x*x), -2.0, 4.0));
+    assert_eq!(1.0/x, Interval::entire());
+    assert!(contains(&Interval::new(0.0, 3.0).sin(), 0.0, 1.0));
+    assert!(contains(&Interval::new(1.0, 4.0).cos(), -1.0, 1f64.cos()));
+}
+
+// Whether every sampled value of `f` over `x` falls in `f(x)`.
+fn encloses(x: Interval, f: impl Fn(f64) -> f64, f_interval: impl Fn(Interval) -> Interval) -> bool {
+    let result = f_interval(x);
+    (0..=100)
+        .map(|step| x.lo + x.width()*f64::from(step)/100.0)
+        .all(|value| result.contains(f(value)))
+}
+
+#[test]
+fn every_method_of_dual_is_enclosed() {
+    let x = Interval::new(-0.5, 1.2);
+    assert!(encloses(x, f64::tan, Interval::tan));
+    assert!(encloses(Interval::new(-0.5, 0.9), f64::asin, Interval::asin));
+    assert!(encloses(Interval::new(-0.5, 0.9), f64::acos, Interval::acos));
+    assert!(encloses(Interval::new(0.5, 2.0), |x| x.powf(1.5), |x| x.powf(1.5)));
+    assert!(encloses(Interval::new(0.5, 2.0), |x| x.powf(-0.5), |x| x.powf(-0.5)));
+    assert!(encloses(x, |x| x.powf(3.0), |x| x.powf(3.0)));
+    assert!(encloses(x, |x| x.atan2(2.0), |x| x.atan2(2.0)));
+    assert!(encloses(x, |x| 1.0f64.atan2(x), |x| Interval::point(1.0).atan2(x)));
+    assert_eq!(Interval::new(1.0, 2.0).tan(), Interval::entire());
+    assert!(Interval::new(-1.0, 1.0).atan2(Interval::new(-1.0, 1.0)).contains(std::f64::consts::PI));
+}
+
+create_constrainer!(
+    #[intervals]
+    Ramp {
+        dynamic rise f64
+        dynamic run f64
+        constrained angle f64 (rise, run) {
+            rise.atan2(*run)
+        }
+        constrained slope f64 (angle) {
+            angle.tan()
+        }
+        constrained curve f64 (run) {
+            run.powf(1.5)
+        }
+    }
+);
+
+#[test]
+fn bodies_using_the_methods_of_dual_are_evaluated() {
+    let ramp = Ramp::new(1.0, 2.0);
+    let view = Ramp::interval_view(Interval::around(1.0, 0.1), Interval::around(2.0, 0.1));
+    assert_eq!((*ramp.get_rise(), *ramp.get_run()), (1.0, 2.0));
+    assert!(view.get_angle().contains(*ramp.get_angle()));
+    assert!(view.get_slope().contains(*ramp.get_slope()));
+    assert!(view.get_curve().contains(*ramp.get_curve()));
+    assert!(contains(view.get_slope(), 0.9/2.1, 1.1/1.9));
+}
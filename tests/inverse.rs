@@ -0,0 +1,71 @@
+use constrainer::create_constrainer;
+use std::cell::RefCell;
+
+create_constrainer!(
+    #[setters(all)]
+    Tank {
+        dynamic width f32
+        dynamic height f32
+        dynamic depth f32 where *depth > 0.0
+        external log &RefCell<Vec<f32>>
+        constrained double_width f32 (width) {
+            width*2.0
+        } inverse {
+            width = double_width/2.0
+        }
+        constrained area f32 (width, height) {
+            width*height
+        } inverse {
+            width = area/height;
+        }
+        constrained volume f32 (area, depth) {
+            area*depth
+        } inverse {
+            depth = volume/area;
+        }
+        constrained size (f32, f32) (width, height) {
+            (*width, *height)
+        } inverse {
+            width = size.0;
+            height = size.1;
+        }
+        listener report (area, log) {
+            log.borrow_mut().push(*area);
+        }
+    }
+);
+
+#[test]
+fn setting_a_constrained_sets_its_dynamics() {
+    let log = RefCell::new(Vec::new());
+    let mut tank = Tank::new(2.0, 3.0, 1.0, &log).unwrap();
+    log.borrow_mut().clear();
+
+    let changed = tank.set_double_width(8.0, &log).unwrap();
+    assert!(changed.width() && changed.double_width() && changed.area() && changed.volume());
+    assert!(!changed.height());
+    assert_eq!((*tank.get_width(), *tank.get_area(), *tank.get_volume()), (4.0, 12.0, 12.0));
+
+    tank.set_area(6.0, &log).unwrap();
+    assert_eq!((*tank.get_width(), *tank.get_double_width()), (2.0, 4.0));
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![12.0, 6.0]);
+}
+
+#[test]
+fn inverse_setters_normalize_and_check() {
+    let log = RefCell::new(Vec::new());
+    let mut tank = Tank::new(2.0, 3.0, 1.0, &log).unwrap();
+
+    tank.set_volume(18.0).unwrap();
+    assert_eq!(*tank.get_depth(), 3.0);
+    assert!(tank.set_volume(-6.0).is_err());
+    assert_eq!(*tank.get_depth(), 3.0);
+}
+
+#[test]
+fn inverse_can_set_several_dynamics() {
+    let log = RefCell::new(Vec::new());
+    let mut tank = Tank::new(2.0, 3.0, 1.0, &log).unwrap();
+    tank.set_size((5.0, 4.0), &log).unwrap();
+    assert_eq!((*tank.get_width(), *tank.get_height(), *tank.get_area()), (5.0, 4.0, 20.0));
+}
@@ -0,0 +1,63 @@
+use constrainer::create_constrainer;
+use std::cell::RefCell;
+
+thread_local! {
+    static CALLS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+fn call(name: &'static str) {
+    CALLS.with(|calls| calls.borrow_mut().push(name));
+}
+
+fn take_calls() -> Vec<&'static str> {
+    CALLS.with(|calls| calls.take())
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Signal {
+        dynamic samples u32
+        dynamic frequency f64
+        lazy constrained spectrum f64 (samples, frequency) {
+            call("spectrum");
+            f64::from(*samples)*frequency
+        }
+        lazy constrained peak f64 (spectrum, samples) {
+            call("peak");
+            spectrum/f64::from(*samples)
+        }
+        constrained nyquist f64 (frequency) {
+            call("nyquist");
+            frequency/2.0
+        }
+    }
+);
+
+#[test]
+fn computed_on_first_get() {
+    let signal = Signal::new(10, 0.5);
+    assert_eq!(take_calls(), vec!["nyquist"]);
+
+    assert_eq!(*signal.get_peak(), 0.5);
+    assert_eq!(take_calls(), vec!["spectrum", "peak"]);
+    assert_eq!(*signal.get_peak(), 0.5);
+    assert_eq!(*signal.get_spectrum(), 5.0);
+    assert!(take_calls().is_empty());
+}
+
+#[test]
+fn setters_only_invalidate() {
+    let mut signal = Signal::new(10, 0.5);
+    signal.get_peak();
+    take_calls();
+
+    signal.set_samples(20);
+    signal.set_frequency(0.25);
+    assert_eq!(take_calls(), vec!["nyquist"]);
+
+    assert_eq!(*signal.get_spectrum(), 5.0);
+    assert_eq!(take_calls(), vec!["spectrum"]);
+    assert_eq!(*signal.get_peak(), 0.25);
+    assert_eq!(*signal.get_peak(), 0.25);
+    assert_eq!(take_calls(), vec!["peak"]);
+}
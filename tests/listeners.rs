@@ -0,0 +1,146 @@
+use constrainer::create_constrainer;
+use std::cell::RefCell;
+
+#[derive(Debug, PartialEq)]
+enum Call {
+    Report(f32, f32, f32),
+    Sum(f32, f32),
+    Parity(bool),
+}
+
+create_constrainer!(
+    #[setters(all, group(x, y))]
+    Mixed {
+        dynamic x f32
+        dynamic y f32
+        dynamic z f32
+        external log &RefCell<Vec<Call>>
+        constrained xy f32 (x, y) {
+            x*y
+        }
+        constrained xy_z f32 (xy, z) {
+            xy+z
+        }
+        listener report (x, y, xy, log) {
+            log.borrow_mut().push(Call::Report(*x, *y, *xy));
+        }
+        listener sum (z, xy_z, log) {
+            log.borrow_mut().push(Call::Sum(*z, *xy_z));
+        }
+    }
+);
+
+#[test]
+fn listeners_are_called_by_new() {
+    let log = RefCell::new(Vec::new());
+    Mixed::new(2.0, 3.0, 4.0, &log);
+    assert_eq!(log.into_inner(), vec![
+        Call::Report(2.0, 3.0, 6.0),
+        Call::Sum(4.0, 10.0),
+    ]);
+}
+
+#[test]
+fn listener_receives_set_untouched_and_recomputed_params() {
+    let log = RefCell::new(Vec::new());
+    let mut mixed = Mixed::new(2.0, 3.0, 4.0, &log);
+    log.borrow_mut().clear();
+
+    mixed.set_x(5.0, &log);
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Report(5.0, 3.0, 15.0),
+        Call::Sum(4.0, 19.0),
+    ]);
+
+    mixed.set_y(1.0, &log);
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Report(5.0, 1.0, 5.0),
+        Call::Sum(4.0, 9.0),
+    ]);
+}
+
+#[test]
+fn listener_receives_untouched_constrained() {
+    let log = RefCell::new(Vec::new());
+    let mut mixed = Mixed::new(2.0, 3.0, 4.0, &log);
+    log.borrow_mut().clear();
+
+    mixed.set_z(1.0, &log);
+    assert_eq!(log.into_inner(), vec![
+        Call::Sum(1.0, 7.0),
+    ]);
+}
+
+#[test]
+fn group_setter_calls_each_listener_once() {
+    let log = RefCell::new(Vec::new());
+    let mut mixed = Mixed::new(2.0, 3.0, 4.0, &log);
+    log.borrow_mut().clear();
+
+    mixed.set_x_y(1.0, 1.0, &log);
+    assert_eq!(log.into_inner(), vec![
+        Call::Report(1.0, 1.0, 1.0),
+        Call::Sum(4.0, 5.0),
+    ]);
+}
+
+#[test]
+fn transaction_calls_listeners_with_committed_values() {
+    let log = RefCell::new(Vec::new());
+    let mut mixed = Mixed::new(2.0, 3.0, 4.0, &log);
+    log.borrow_mut().clear();
+
+    mixed.update(&log, |mixed| {
+        mixed.set_z(0.0).set_x(1.0);
+    });
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Report(1.0, 3.0, 3.0),
+        Call::Sum(0.0, 3.0),
+    ]);
+
+    mixed.update(&log, |mixed| {
+        mixed.set_z(2.0);
+    });
+    assert_eq!(log.into_inner(), vec![
+        Call::Sum(2.0, 5.0),
+    ]);
+}
+
+create_constrainer!(
+    #[cutoff]
+    #[setters(all)]
+    Parity {
+        dynamic n u32
+        external log &RefCell<Vec<Call>>
+        constrained even bool (n) {
+            n % 2 == 0
+        }
+        lazy constrained half u32 (n) {
+            n/2
+        }
+        listener parity (even, log) {
+            log.borrow_mut().push(Call::Parity(*even));
+        }
+        listener halves (n, half, log) {
+            log.borrow_mut().push(Call::Sum(*n as f32, *half as f32));
+        }
+    }
+);
+
+#[test]
+fn cutoff_skips_listeners_of_unchanged_constraineds() {
+    let log = RefCell::new(Vec::new());
+    let mut parity = Parity::new(2, &log);
+    log.borrow_mut().clear();
+
+    parity.set_n(4, &log);
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Sum(4.0, 2.0),
+    ]);
+
+    parity.set_n(5, &log);
+    assert_eq!(log.into_inner(), vec![
+        Call::Parity(false),
+        Call::Sum(5.0, 2.0),
+    ]);
+}
@@ -0,0 +1,35 @@
+use constrainer::create_constrainer;
+
+// Names the generated code also needs, which must not clash with it.
+create_constrainer!(
+    #[setters(all)]
+    Clash {
+        dynamic constrainer f32
+        dynamic f f32
+        external transaction &f32
+        external value &f32
+        constrained sum f32 (constrainer, f, transaction, value) {
+            constrainer + f + **transaction + **value
+        }
+    }
+);
+
+#[test]
+fn generated_names_do_not_clash() {
+    let mut clash = Clash::new(1.0, 2.0, &3.0, &4.0);
+    assert_eq!(*clash.get_sum(), 10.0);
+
+    clash.update(&1.0, &1.0, |transaction| {
+        transaction.set_constrainer(5.0).set_f(6.0);
+    });
+    assert_eq!((*clash.get_constrainer(), *clash.get_f()), (5.0, 6.0));
+    assert_eq!(*clash.get_sum(), 13.0);
+
+    let mut transaction = clash.begin();
+    transaction.set_f(0.0);
+    transaction.commit(&0.0, &0.0);
+    assert_eq!(*clash.get_sum(), 5.0);
+
+    clash.set_constrainer(1.0, &0.0, &0.0);
+    assert_eq!(*clash.get_sum(), 1.0);
+}
@@ -0,0 +1,37 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(
+    #[setters(all)]
+    Heading {
+        dynamic angle f32 normalize { angle.rem_euclid(360.0) } where *angle < 180.0
+        dynamic speed f32 normalize { speed.clamp(0.0, 10.0) }
+        constrained quadrant u8 (angle) {
+            (angle/90.0) as u8
+        }
+    }
+);
+
+#[test]
+fn new_normalizes_before_checking() {
+    let heading = Heading::new(-270.0, 20.0).unwrap();
+    assert_eq!(*heading.get_angle(), 90.0);
+    assert_eq!(*heading.get_speed(), 10.0);
+    assert_eq!(*heading.get_quadrant(), 1);
+    assert!(Heading::new(-90.0, 1.0).is_err());
+}
+
+#[test]
+fn setters_and_transactions_normalize() {
+    let mut heading = Heading::new(0.0, 0.0).unwrap();
+    heading.set_angle(450.0).unwrap();
+    assert_eq!((*heading.get_angle(), *heading.get_quadrant()), (90.0, 1));
+    heading.set_speed(-5.0).unwrap();
+    assert_eq!(*heading.get_speed(), 0.0);
+
+    heading.update(|transaction| {
+        transaction.set_angle(-315.0).set_speed(11.0);
+    }).unwrap();
+    assert_eq!((*heading.get_angle(), *heading.get_speed(), *heading.get_quadrant()), (45.0, 10.0, 0));
+    assert!(heading.set_angle(200.0).is_err());
+    assert_eq!(*heading.get_angle(), 45.0);
+}
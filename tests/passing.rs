@@ -0,0 +1,63 @@
+use constrainer::create_constrainer;
+use std::cell::Cell;
+
+// Counts its clones, to tell passing by reference from passing a clone.
+#[derive(Debug, PartialEq)]
+struct Name {
+    text: String,
+    clones: Cell<u32>,
+}
+
+impl Name {
+    fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            clones: Cell::new(0),
+        }
+    }
+}
+
+impl Clone for Name {
+    fn clone(&self) -> Self {
+        self.clones.set(self.clones.get() + 1);
+        Self::new(&self.text)
+    }
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Tag {
+        dynamic name Name
+        dynamic count u32
+        constrained borrowed usize (name) {
+            name.text.len()
+        }
+        constrained owned Name (clone name) {
+            let mut name = name;
+            name.text.push('!');
+            name
+        }
+        constrained doubled u32 (move count) {
+            let count: u32 = count;
+            count*2
+        }
+    }
+);
+
+#[test]
+fn params_are_borrowed_moved_or_cloned() {
+    let mut tag = Tag::new(Name::new("tag"), 2);
+    assert_eq!(*tag.get_borrowed(), 3);
+    assert_eq!(tag.get_owned().text, "tag!");
+    assert_eq!(*tag.get_doubled(), 4);
+    // Cloned once, for `owned` only.
+    assert_eq!(tag.get_name().clones.get(), 1);
+
+    tag.set_count(5);
+    assert_eq!(*tag.get_doubled(), 10);
+    assert_eq!(tag.get_name().clones.get(), 1);
+
+    tag.set_name(Name::new("label"));
+    assert_eq!(tag.get_owned().text, "label!");
+    assert_eq!(tag.get_name().clones.get(), 1);
+}
@@ -0,0 +1,57 @@
+use constrainer::create_constrainer;
+use std::cell::RefCell;
+
+create_constrainer!(
+    #[setters(all, group(width, height), group(height, depth))]
+    Cuboid {
+        dynamic width f32
+        dynamic height f32
+        dynamic depth f32
+        external calls &RefCell<Vec<&'static str>>
+        constrained area f32 (width, height, calls) {
+            calls.borrow_mut().push("area");
+            width*height
+        }
+        constrained volume f32 (area, depth, calls) {
+            calls.borrow_mut().push("volume");
+            area*depth
+        }
+        listener report (volume, calls) {
+            if *volume > 0.0 {
+                calls.borrow_mut().push("report");
+            }
+        }
+    }
+);
+
+#[test]
+fn every_dynamic_has_a_setter() {
+    let calls = RefCell::new(Vec::new());
+    let mut cuboid = Cuboid::new(1.0, 2.0, 3.0, &calls);
+    calls.borrow_mut().clear();
+
+    cuboid.set_width(2.0, &calls);
+    assert_eq!(calls.take(), vec!["area", "volume", "report"]);
+    assert_eq!((*cuboid.get_area(), *cuboid.get_volume()), (4.0, 12.0));
+
+    // Only what depends on `depth` is recomputed.
+    cuboid.set_depth(0.5, &calls);
+    assert_eq!(calls.take(), vec!["volume", "report"]);
+    assert_eq!(*cuboid.get_volume(), 2.0);
+}
+
+#[test]
+fn groups_set_dynamics_together() {
+    let calls = RefCell::new(Vec::new());
+    let mut cuboid = Cuboid::new(1.0, 2.0, 3.0, &calls);
+    calls.borrow_mut().clear();
+
+    cuboid.set_width_height(3.0, 3.0, &calls);
+    assert_eq!(calls.take(), vec!["area", "volume", "report"]);
+    assert_eq!((*cuboid.get_width(), *cuboid.get_height()), (3.0, 3.0));
+    assert_eq!(*cuboid.get_volume(), 27.0);
+
+    cuboid.set_height_depth(1.0, 2.0, &calls);
+    assert_eq!(calls.take(), vec!["area", "volume", "report"]);
+    assert_eq!((*cuboid.get_area(), *cuboid.get_volume()), (3.0, 6.0));
+}
@@ -0,0 +1,90 @@
+use constrainer::{
+    create_constrainer,
+    sketch::Point,
+    ConstrainerError,
+};
+
+create_constrainer!(
+    #[setters(all)]
+    Plate {
+        dynamic width f64
+        dynamic height f64
+        dynamic hole f64
+        sketch profile {
+            point a (0.0, 0.0)
+            point b (3.0, 0.1)
+            point c (3.2, 2.0)
+            point d (0.1, 2.1)
+            point center (1.0, 1.0)
+            line bottom (a, b)
+            line right (b, c)
+            line top (c, d)
+            line left (d, a)
+            circle bore (center, 0.5)
+            fix (a) == (0.0, 0.0)
+            horizontal (bottom)
+            perpendicular (bottom, right)
+            parallel (top, bottom)
+            parallel (left, right)
+            distance (a, b) == width
+            distance (b, c) == height
+            radius (bore) == hole
+            tangent (bottom, bore)
+            tangent (bore, left)
+        }
+        constrained area f64 (bottom, right) {
+            bottom.length()*right.length()
+        }
+    }
+);
+
+fn close(a: Point, b: Point) -> bool {
+    a.distance(b) < 1e-6
+}
+
+#[test]
+fn sketch_is_solved() {
+    let plate = Plate::new(4.0, 2.0, 0.5).unwrap();
+    assert!(close(*plate.get_b(), Point::new(4.0, 0.0)));
+    assert!(close(*plate.get_c(), Point::new(4.0, 2.0)));
+    assert!(close(*plate.get_d(), Point::new(0.0, 2.0)));
+    assert!(close(plate.get_bore().center, Point::new(0.5, 0.5)));
+    assert!((plate.get_top().length() - 4.0).abs() < 1e-6);
+    assert!((plate.get_area() - 8.0).abs() < 1e-6);
+}
+
+#[test]
+fn dimensions_update_the_sketch() {
+    let mut plate = Plate::new(4.0, 2.0, 0.5).unwrap();
+    let changed = plate.set_width(6.0).unwrap();
+    assert!(changed.b() && changed.c() && changed.bottom() && changed.area());
+    assert!(close(*plate.get_c(), Point::new(6.0, 2.0)));
+    assert!((plate.get_area() - 12.0).abs() < 1e-6);
+
+    plate.set_hole(0.75).unwrap();
+    assert!(close(*plate.get_center(), Point::new(0.75, 0.75)));
+    assert!((plate.get_bore().radius - 0.75).abs() < 1e-6);
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Bar {
+        dynamic end f64
+        sketch bar {
+            point a (0.0, 0.0)
+            point b (1.0, 0.0)
+            fix (a) == (0.0, 0.0)
+            fix (b) == (*end, 0.0)
+            distance (a, b) == 1.0
+        }
+    }
+);
+
+#[test]
+fn conflicting_constraints_are_rolled_back() {
+    let mut bar = Bar::new(1.0).unwrap();
+    let error = bar.set_end(3.0).unwrap_err();
+    assert!(matches!(error, ConstrainerError::Solve { unknowns: "bar", .. }));
+    assert_eq!(*bar.get_end(), 1.0);
+    assert!(close(*bar.get_b(), Point::new(1.0, 0.0)));
+}
@@ -0,0 +1,80 @@
+use constrainer::{
+    create_constrainer,
+    ConstrainerError,
+};
+
+create_constrainer!(
+    #[setters(all)]
+    Triangle {
+        dynamic r f64
+        dynamic ratio f64
+        solve (a, b) {
+            a*a + b*b == r*r;
+            a == ratio*b
+        }
+        constrained perimeter f64 (a, b, r) {
+            a + b + r
+        }
+    }
+);
+
+fn close(a: f64, b: f64) -> bool {
+    (a-b).abs() < 1e-6
+}
+
+#[test]
+fn unknowns_are_solved_for() {
+    let triangle = Triangle::new(5.0, 4.0/3.0).unwrap();
+    assert!(close(*triangle.get_a(), 4.0));
+    assert!(close(*triangle.get_b(), 3.0));
+    assert!(close(*triangle.get_perimeter(), 12.0));
+}
+
+#[test]
+fn setters_solve_again() {
+    let mut triangle = Triangle::new(5.0, 4.0/3.0).unwrap();
+    let changed = triangle.set_r(10.0).unwrap();
+    assert!(changed.r() && changed.a() && changed.b() && changed.perimeter());
+    assert!(!changed.ratio());
+    assert!(close(*triangle.get_a(), 8.0));
+    assert!(close(*triangle.get_b(), 6.0));
+    assert!(close(*triangle.get_perimeter(), 24.0));
+}
+
+create_constrainer!(
+    #[setters(all)]
+    Intersection {
+        dynamic radius f64
+        dynamic offset f64
+        solve (x = -0.5, y = 2.0) {
+            x*x + y*y == radius*radius;
+            y == offset
+        }
+    }
+);
+
+#[test]
+fn guesses_pick_the_solution() {
+    let intersection = Intersection::new(2.0, 1.0).unwrap();
+    assert!(close(*intersection.get_x(), -3f64.sqrt()));
+    assert!(close(*intersection.get_y(), 1.0));
+}
+
+#[test]
+fn solving_starts_from_the_previous_solution() {
+    // Both signs of `x` satisfy the equations, the one closest to the previous solution is kept.
+    let mut intersection = Intersection::new(2.0, 1.0).unwrap();
+    intersection.set_radius(3.0).unwrap();
+    assert!(close(*intersection.get_x(), -8f64.sqrt()));
+}
+
+#[test]
+fn unsolvable_equations_are_rolled_back() {
+    let mut intersection = Intersection::new(2.0, 1.0).unwrap();
+    let error = intersection.set_offset(3.0).unwrap_err();
+    assert!(matches!(error, ConstrainerError::Solve { unknowns: "(x, y)", .. }));
+    assert_eq!(*intersection.get_offset(), 1.0);
+    assert!(close(*intersection.get_x(), -3f64.sqrt()));
+
+    assert!(matches!(Intersection::new(1.0, 2.0), Err(ConstrainerError::Solve { .. })));
+}
@@ -0,0 +1,53 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(
+    #[setters(all)]
+    Counted {
+        dynamic x f32
+        dynamic y f32
+        state updates u32
+        state history Vec<f32>
+        constrained sum f32 (x, y) {
+            x+y
+        }
+        lazy constrained double f32 (sum) {
+            sum*2.0
+        }
+        listener count (sum, mut updates, mut history) {
+            *updates += 1;
+            history.push(*sum);
+        }
+        listener record (double, mut history) {
+            history.push(*double);
+        }
+    }
+);
+
+#[test]
+fn listeners_modify_state_in_new() {
+    let counted = Counted::new(1.0, 2.0, 0, Vec::new());
+    assert_eq!(*counted.get_updates(), 1);
+    assert_eq!(counted.get_history(), &vec![3.0, 6.0]);
+}
+
+#[test]
+fn listeners_modify_state_in_setters_and_transactions() {
+    let mut counted = Counted::new(1.0, 2.0, 0, Vec::new());
+    counted.set_x(2.0);
+    assert_eq!(*counted.get_updates(), 2);
+    counted.update(|transaction| {
+        transaction.set_x(0.0).set_y(1.0);
+    });
+    assert_eq!(*counted.get_updates(), 3);
+    assert_eq!(counted.get_history(), &vec![3.0, 6.0, 4.0, 8.0, 1.0, 2.0]);
+}
+
+#[test]
+fn state_can_be_reset_from_outside() {
+    let mut counted = Counted::new(1.0, 2.0, 0, Vec::new());
+    *counted.get_updates_mut() = 0;
+    counted.get_history_mut().clear();
+    counted.set_y(0.0);
+    assert_eq!(*counted.get_updates(), 1);
+    assert_eq!(counted.get_history(), &vec![1.0, 2.0]);
+}
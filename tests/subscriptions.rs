@@ -0,0 +1,123 @@
+use constrainer::create_constrainer;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Debug, PartialEq)]
+enum Call {
+    Price(f32),
+    Total(f32),
+    Listener(f32),
+    Doubled(f32),
+}
+
+create_constrainer!(
+    #[subscriptions]
+    #[setters(all)]
+    Order {
+        dynamic price f32
+        dynamic quantity f32
+        external log &RefCell<Vec<Call>>
+        constrained total f32 (price, quantity) {
+            price*quantity
+        }
+        lazy constrained doubled f32 (total) {
+            total*2.0
+        }
+        listener total_listener (total, log) {
+            log.borrow_mut().push(Call::Listener(*total));
+        }
+    }
+);
+
+fn logging(log: &Rc<RefCell<Vec<Call>>>, call: fn(f32) -> Call) -> Box<dyn FnMut(&f32)> {
+    let log = log.clone();
+    Box::new(move |value| log.borrow_mut().push(call(*value)))
+}
+
+#[test]
+fn setters_notify_subscribers_in_listener_order() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut order = Order::new(2.0, 3.0, &log);
+    order.subscribe_total(logging(&log, Call::Total));
+    order.subscribe_price(logging(&log, Call::Price));
+    log.borrow_mut().clear();
+
+    order.set_price(4.0, &log);
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Price(4.0),
+        Call::Total(12.0),
+        Call::Listener(12.0),
+    ]);
+
+    order.set_quantity(1.0, &log);
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Total(4.0),
+        Call::Listener(4.0),
+    ]);
+}
+
+#[test]
+fn transactions_notify_only_what_was_set() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut order = Order::new(2.0, 3.0, &log);
+    order.subscribe_price(logging(&log, Call::Price));
+    order.subscribe_doubled(logging(&log, Call::Doubled));
+    log.borrow_mut().clear();
+
+    order.update(&log, |transaction| {
+        transaction.set_quantity(5.0);
+    });
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Doubled(20.0),
+        Call::Listener(10.0),
+    ]);
+}
+
+#[test]
+fn unsubscribed_closures_are_not_called() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut order = Order::new(2.0, 3.0, &log);
+    let first = order.subscribe_total(logging(&log, Call::Total));
+    let second = order.subscribe_total(logging(&log, Call::Doubled));
+    assert_ne!(first, second);
+    assert!(order.unsubscribe(first));
+    assert!(!order.unsubscribe(first));
+    log.borrow_mut().clear();
+
+    order.set_price(1.0, &log);
+    assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), vec![
+        Call::Doubled(3.0),
+        Call::Listener(3.0),
+    ]);
+}
+
+create_constrainer!(
+    #[subscriptions]
+    #[cutoff]
+    #[setters(all)]
+    Thermostat {
+        dynamic celsius f32
+        constrained heating bool (celsius) {
+            *celsius < 18.0
+        }
+    }
+);
+
+#[test]
+fn cutoff_only_notifies_subscribers_of_actual_changes() {
+    let temperatures = Rc::new(RefCell::new(Vec::new()));
+    let heating = Rc::new(RefCell::new(Vec::new()));
+    let mut thermostat = Thermostat::new(20.0);
+    let recorded = temperatures.clone();
+    thermostat.subscribe_celsius(Box::new(move |celsius| recorded.borrow_mut().push(*celsius)));
+    let recorded = heating.clone();
+    thermostat.subscribe_heating(Box::new(move |heating| recorded.borrow_mut().push(*heating)));
+
+    thermostat.set_celsius(15.0);
+    thermostat.set_celsius(10.0);
+    thermostat.update(|transaction| {
+        transaction.set_celsius(21.0);
+    });
+    assert_eq!(*temperatures.borrow(), vec![15.0, 10.0, 21.0]);
+    assert_eq!(*heating.borrow(), vec![true, false]);
+}
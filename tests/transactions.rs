@@ -0,0 +1,57 @@
+use constrainer::create_constrainer;
+use std::cell::RefCell;
+
+create_constrainer!(Mixer {
+    dynamic red f32
+    dynamic green f32
+    dynamic blue f32
+    dynamic alpha f32
+    external calls &RefCell<Vec<&'static str>>
+    constrained brightness f32 (red, green, blue, calls) {
+        calls.borrow_mut().push("brightness");
+        (red+green+blue)/3.0
+    }
+    constrained warmth f32 (red, blue, calls) {
+        calls.borrow_mut().push("warmth");
+        red-blue
+    }
+    constrained tone f32 (brightness, warmth, calls) {
+        calls.borrow_mut().push("tone");
+        brightness*warmth
+    }
+    constrained opacity f32 (alpha, calls) {
+        calls.borrow_mut().push("opacity");
+        *alpha
+    }
+});
+
+#[test]
+fn commit_recomputes_each_affected_constrained_once() {
+    let calls = RefCell::new(Vec::new());
+    let mut mixer = Mixer::new(0.25, 0.5, 0.75, 1.0, &calls);
+    calls.borrow_mut().clear();
+
+    mixer.update(&calls, |transaction| {
+        transaction.set_red(1.0).set_green(0.5);
+        transaction.set_blue(0.0).set_red(0.5);
+    });
+    assert_eq!(calls.take(), vec!["brightness", "warmth", "tone"]);
+    assert_eq!((*mixer.get_brightness(), *mixer.get_warmth(), *mixer.get_tone()), (1.0/3.0, 0.5, 1.0/6.0));
+
+    let mut transaction = mixer.begin();
+    transaction.set_green(1.0).set_alpha(0.5);
+    transaction.commit(&calls);
+    assert_eq!(calls.take(), vec!["brightness", "tone", "opacity"]);
+    assert_eq!(*mixer.get_opacity(), 0.5);
+}
+
+#[test]
+fn dropped_transactions_recompute_nothing() {
+    let calls = RefCell::new(Vec::new());
+    let mut mixer = Mixer::new(0.25, 0.5, 0.75, 1.0, &calls);
+    calls.borrow_mut().clear();
+
+    mixer.begin().set_red(0.0).set_alpha(0.0);
+    assert!(calls.borrow().is_empty());
+    assert_eq!((*mixer.get_red(), *mixer.get_alpha()), (0.25, 1.0));
+}
@@ -0,0 +1,47 @@
+use constrainer::create_constrainer;
+use std::collections::HashMap;
+use std::time::Duration;
+
+create_constrainer!(
+    #[setters(all)]
+    Track {
+        dynamic samples Vec<f32>
+        dynamic step std::time::Duration
+        dynamic origin [f32; 3]
+        dynamic transform fn(f32) -> f32
+        external unit &'static str
+        constrained length Duration (samples, step) {
+            *step*samples.len() as u32
+        }
+        constrained range (f32, f32) (samples) {
+            samples.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), sample| (lo.min(*sample), hi.max(*sample)))
+        }
+        constrained shifted [f32; 3] (origin, range, transform) {
+            origin.map(|coordinate| transform(coordinate + range.0))
+        }
+        constrained counts HashMap<String, usize> (samples, unit) {
+            HashMap::from([(unit.to_string(), samples.len())])
+        }
+        constrained first Option<f32> (samples) {
+            samples.first().copied()
+        }
+    }
+);
+
+#[test]
+fn any_type_can_be_used() {
+    let mut track = Track::new(vec![1.0, -2.0, 3.0], Duration::from_millis(10), [0.0, 1.0, 2.0], |x| x*2.0, "samples");
+    assert_eq!(*track.get_length(), Duration::from_millis(30));
+    assert_eq!(*track.get_range(), (-2.0, 3.0));
+    assert_eq!(*track.get_shifted(), [-4.0, -2.0, 0.0]);
+    assert_eq!(track.get_counts()["samples"], 3);
+    assert_eq!(*track.get_first(), Some(1.0));
+
+    track.set_samples(Vec::new(), "none");
+    assert_eq!(*track.get_length(), Duration::ZERO);
+    assert_eq!(track.get_counts()["none"], 0);
+    assert_eq!(*track.get_first(), None);
+
+    track.set_transform(|x| x + 1.0);
+    assert_eq!(track.get_shifted()[0], f32::INFINITY);
+}
@@ -0,0 +1,7 @@
+// Compile errors reported by the macros, checked against the `.stderr` snapshots next to each
+// case. Run with `TRYBUILD=overwrite` to update them after changing a message.
+#[test]
+fn ui() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
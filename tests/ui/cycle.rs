@@ -0,0 +1,16 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(Loop {
+    dynamic x f32
+    constrained a f32 (x, c) {
+        x+c
+    }
+    constrained b f32 (a) {
+        *a
+    }
+    constrained c f32 (b) {
+        *b
+    }
+});
+
+fn main() {}
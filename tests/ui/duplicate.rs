@@ -0,0 +1,11 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(Rectangle {
+    dynamic width f32
+    dynamic height f32
+    constrained width f32 (height) {
+        *height
+    }
+});
+
+fn main() {}
@@ -0,0 +1,21 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(
+    #[setters(group(widht))]
+    Shape {
+        dynamic width f32
+        dynamic height f32
+        dynamic width f32
+        constrained area f32 (width, heigth) {
+            width*heigth
+        }
+        constrained perimeter f32 (ratio) {
+            *ratio
+        }
+        constrained ratio f32 (perimeter) {
+            *perimeter
+        }
+    }
+);
+
+fn main() {}
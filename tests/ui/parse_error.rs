@@ -0,0 +1,10 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(Rectangle {
+    dynamic width f32
+    constrained area f32 width {
+        *width
+    }
+});
+
+fn main() {}
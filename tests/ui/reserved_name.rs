@@ -0,0 +1,10 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(Queue {
+    dynamic length u32
+    constrained is_empty bool (length) {
+        *length == 0
+    }
+});
+
+fn main() {}
@@ -0,0 +1,10 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(Counter {
+    dynamic step u32
+    constrained total u32 (total, step) {
+        total+step
+    }
+});
+
+fn main() {}
@@ -0,0 +1,14 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(Rectangle {
+    dynamic width f32
+    dynamic height f32
+    constrained area f32 (width, heigt) {
+        width*height
+    }
+    constrained label String (area, unit) {
+        format!("{}{}", area, unit)
+    }
+});
+
+fn main() {}
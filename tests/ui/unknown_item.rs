@@ -0,0 +1,8 @@
+use constrainer::create_constrainer;
+
+create_constrainer!(Rectangle {
+    dynamic width f32
+    variable height f32
+});
+
+fn main() {}